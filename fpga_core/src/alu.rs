@@ -15,33 +15,348 @@ pub fn fixed_mul(a: Fixed32, b: Fixed32) -> Fixed32 {
     (prod >> 16).resize()
 }
 
-// adunare vectoriala
+// coordonata unui punct, taiata la 25 de biti pentru portul lung al DSP48 (25x18)
+// in 16.16 asta inseamna +-256 m; un punct mai departe e saturat la margine (mult peste distanta unui LiDAR)
+#[kernel]
+pub fn point_slice(v: Fixed32) -> PointFixed {
+    let mut result: PointFixed = v.resize();
+    if v > signed(0xFF_FFFF) {
+        result = signed(0xFF_FFFF);
+    }
+    if v < signed(-0x100_0000) {
+        result = signed(-0x100_0000);
+    }
+    result
+}
+
+// inmultire coeficient de rotatie (Q2.16, 18 biti) * coordonata de punct (16.16, 25 biti)
+// 18x25 e exact un DSP48; produsul are 43 de biti si ramane in 16.16 dupa shiftarea cu 16
+// |r| < 2 si |v| < 256, deci rezultatul incape pe 26 de biti; il intoarcem pe 36 (vezi rot_row_dot)
+#[kernel]
+pub fn fixed_mul_rot(r: RotFixed, v: PointFixed) -> s36 {
+    let r_wide: s43 = r.resize();
+    let v_wide: s43 = v.resize();
+
+    let prod = r_wide * v_wide;
+
+    (prod >> 16).resize()
+}
+
+// acelasi coeficient inmultit cu o coordonata 16.16 completa (translatiile din pose-uri, care pot depasi 256 m)
+// 18x32 ia 2 felii DSP, dar ruleaza doar la scrierea pose-ului, nu pe fiecare punct
+#[kernel]
+pub fn fixed_mul_rot_wide(r: RotFixed, v: Fixed32) -> s36 {
+    let r_wide: s50 = r.resize();
+    let v_wide: s50 = v.resize();
+
+    let prod = r_wide * v_wide;
+
+    (prod >> 16).resize()
+}
+
+// sumele se fac pe 36 de biti si sunt saturate la capetele 16.16, nu intoarse peste semn
+// (un punct care iese din +-32768 m ramane la margine in loc sa sara in partea opusa)
+#[kernel]
+pub fn saturate_fixed(v: s36) -> Fixed32 {
+    let mut result: Fixed32 = v.resize();
+    if v > signed(0x7FFF_FFFF) {
+        result = signed(0x7FFF_FFFF);
+    }
+    if v < signed(-0x8000_0000) {
+        result = signed(-0x8000_0000);
+    }
+    result
+}
+
+// adunare 16.16 cu saturare
+#[kernel]
+pub fn fixed_add_sat(a: Fixed32, b: Fixed32) -> Fixed32 {
+    let a_wide: s36 = a.resize();
+    let b_wide: s36 = b.resize();
+    saturate_fixed(a_wide + b_wide)
+}
+
+// scadere 16.16 cu saturare (0 - (-32768) ramane la +32768 - 2^-16, nu se intoarce la -32768)
+#[kernel]
+pub fn fixed_sub_sat(a: Fixed32, b: Fixed32) -> Fixed32 {
+    let a_wide: s36 = a.resize();
+    let b_wide: s36 = b.resize();
+    saturate_fixed(a_wide - b_wide)
+}
+
+// adunare vectoriala (saturata)
 #[kernel]
 pub fn vector_add(v1: Vector3, v2: Vector3) -> Vector3 {
     Vector3 {
-        x: v1.x + v2.x,
-        y: v1.y + v2.y,
-        z: v1.z + v2.z,
+        x: fixed_add_sat(v1.x, v2.x),
+        y: fixed_add_sat(v1.y, v2.y),
+        z: fixed_add_sat(v1.z, v2.z),
     }
 }
 
-// matrice * vector
+// un rand al matricei * punct: 3 DSP-uri, suma e saturata o singura data, la final
+#[kernel]
+pub fn rot_row_dot(row: [RotFixed; 3], v: Vector3) -> Fixed32 {
+    saturate_fixed(
+        fixed_mul_rot(row[0], point_slice(v.x)) +
+        fixed_mul_rot(row[1], point_slice(v.y)) +
+        fixed_mul_rot(row[2], point_slice(v.z))
+    )
+}
+
+// acelasi rand pe coordonate complete; suma (sub 3 * 2^32) e saturata la final
+#[kernel]
+pub fn rot_row_dot_wide(row: [RotFixed; 3], v: Vector3) -> Fixed32 {
+    saturate_fixed(
+        fixed_mul_rot_wide(row[0], v.x) +
+        fixed_mul_rot_wide(row[1], v.y) +
+        fixed_mul_rot_wide(row[2], v.z)
+    )
+}
+
+// matrice * punct (calea fiecarui punct, 9 DSP-uri; coordonatele limitate la +-256 m, vezi point_slice)
 #[kernel]
 pub fn matrix_vector_mult(m: Matrix3x3, v: Vector3) -> Vector3 {
-    // calculam x
-    let x = fixed_mul(m.rows[0][0], v.x) +
-        fixed_mul(m.rows[0][1], v.y) +
-        fixed_mul(m.rows[0][2], v.z);
-
-    // calculam y
-    let y = fixed_mul(m.rows[1][0], v.x) +
-        fixed_mul(m.rows[1][1], v.y) +
-        fixed_mul(m.rows[1][2], v.z);
-
-    // calculam z
-    let z = fixed_mul(m.rows[2][0], v.x) +
-        fixed_mul(m.rows[2][1], v.y) +
-        fixed_mul(m.rows[2][2], v.z);
-
-    Vector3 { x, y, z }
-}
\ No newline at end of file
+    Vector3 {
+        x: rot_row_dot(m.rows[0], v),
+        y: rot_row_dot(m.rows[1], v),
+        z: rot_row_dot(m.rows[2], v),
+    }
+}
+
+// matrice * translatie, pe tot domeniul 16.16 (compunerea si inversarea pose-urilor)
+#[kernel]
+pub fn matrix_vector_mult_wide(m: Matrix3x3, v: Vector3) -> Vector3 {
+    Vector3 {
+        x: rot_row_dot_wide(m.rows[0], v),
+        y: rot_row_dot_wide(m.rows[1], v),
+        z: rot_row_dot_wide(m.rows[2], v),
+    }
+}
+// inmultire intre doi coeficienti de rotatie (Q2.16 * Q2.16), rezultatul ramane Q2.16
+#[kernel]
+pub fn rot_mul(a: RotFixed, b: RotFixed) -> RotFixed {
+    let a_wide: s36 = a.resize();
+    let b_wide: s36 = b.resize();
+
+    let prod = a_wide * b_wide;
+
+    (prod >> 16).resize()
+}
+
+// matrice * matrice (rotatii compuse)
+#[kernel]
+pub fn matrix_mult(a: Matrix3x3, b: Matrix3x3) -> Matrix3x3 {
+    // pornim de la a.rows doar pentru tip, toate elementele sunt suprascrise
+    let mut rows = a.rows;
+    for i in 0..3 {
+        for j in 0..3 {
+            rows[i][j] = rot_mul(a.rows[i][0], b.rows[0][j]) +
+                rot_mul(a.rows[i][1], b.rows[1][j]) +
+                rot_mul(a.rows[i][2], b.rows[2][j]);
+        }
+    }
+    Matrix3x3 { rows }
+}
+
+// transpusa (inversa unei rotatii)
+#[kernel]
+pub fn matrix_transpose(m: Matrix3x3) -> Matrix3x3 {
+    let mut rows = m.rows;
+    for i in 0..3 {
+        for j in 0..3 {
+            rows[i][j] = m.rows[j][i];
+        }
+    }
+    Matrix3x3 { rows }
+}
+
+// aplicarea unei transformari afine: R * p + t
+#[kernel]
+pub fn affine_apply(m: Matrix4x4, p: Vector3) -> Vector3 {
+    vector_add(matrix_vector_mult(m.rotation, p), m.translation)
+}
+
+// compunere a * b (intai b, apoi a): R = Ra * Rb, t = Ra * tb + ta
+// translatia nu e un punct al senzorului, deci nu trece prin limita de 256 m a lui affine_apply
+#[kernel]
+pub fn affine_compose(a: Matrix4x4, b: Matrix4x4) -> Matrix4x4 {
+    Matrix4x4 {
+        rotation: matrix_mult(a.rotation, b.rotation),
+        translation: vector_add(matrix_vector_mult_wide(a.rotation, b.translation), a.translation),
+    }
+}
+
+// inversa unei transformari rigide: [R^T | -R^T * t]
+#[kernel]
+pub fn affine_inverse(m: Matrix4x4) -> Matrix4x4 {
+    let rt = matrix_transpose(m.rotation);
+    let t = matrix_vector_mult_wide(rt, m.translation);
+    Matrix4x4 {
+        rotation: rt,
+        translation: Vector3 {
+            x: fixed_sub_sat(signed(0), t.x),
+            y: fixed_sub_sat(signed(0), t.y),
+            z: fixed_sub_sat(signed(0), t.z),
+        },
+    }
+}
+
+// 16.16 -> Q2.16 (aceiasi biti de fractie), valabil doar pentru |v| < 2 (unghiuri mici, coeficienti de rotatie)
+#[kernel]
+pub fn fixed_to_rot(v: Fixed32) -> RotFixed {
+    v.resize()
+}
+
+// pose-ul unui punct din viteze constante (aproximare de ordinul 1)
+// theta = omega * dt, R = I + [theta]x, T = velocity * dt
+// eroarea de rotatie e de ordinul theta^2 / 2, deci modul e pentru rotatii mici in timpul unei scanari
+#[kernel]
+pub fn velocity_pose(omega: Vector3, velocity: Vector3, dt: Fixed32) -> Matrix4x4 {
+    let tx = fixed_to_rot(fixed_mul(omega.x, dt));
+    let ty = fixed_to_rot(fixed_mul(omega.y, dt));
+    let tz = fixed_to_rot(fixed_mul(omega.z, dt));
+
+    let one: RotFixed = signed(65536); // 1.0 in Q2.16
+
+    Matrix4x4 {
+        rotation: Matrix3x3 {
+            rows: [
+                [one, -tz, ty],
+                [tz, one, -tx],
+                [-ty, tx, one],
+            ],
+        },
+        translation: Vector3 {
+            x: fixed_mul(velocity.x, dt),
+            y: fixed_mul(velocity.y, dt),
+            z: fixed_mul(velocity.z, dt),
+        },
+    }
+}
+
+// impartire fara semn n / d, restoring, cate un bit de cat pe iteratie (48 de iteratii desfasurate)
+// restul e mereu sub 2 * d < 2^33, deci incape in b48; cu d = 0 catul iese plin de 1
+#[kernel]
+pub fn udiv48(n: b48, d: b48) -> b48 {
+    let mut q: b48 = bits(0);
+    let mut r: b48 = bits(0);
+    for i in 0..48 {
+        r = (r << 1) | ((n >> (47 - i)) & bits(1));
+        q = q << 1;
+        if r >= d {
+            r = r - d;
+            q = q | bits(1);
+        }
+    }
+    q
+}
+
+// radacina patrata intreaga a unui numar pe 64 biti, metoda cifra cu cifra (2 biti de intrare pe iteratie)
+// rezultatul e partea intreaga a radacinii (trunchiat)
+#[kernel]
+pub fn usqrt64(n: b64) -> b32 {
+    let mut root: b36 = bits(0);
+    let mut rem: b36 = bits(0);
+    for i in 0..32 {
+        let pair: b36 = ((n >> (62 - 2 * i)) & bits(3)).resize();
+        rem = (rem << 2) | pair;
+        let trial = (root << 2) | bits(1);
+        root = root << 1;
+        if rem >= trial {
+            rem = rem - trial;
+            root = root | bits(1);
+        }
+    }
+    root.resize()
+}
+
+// modulul unui numar 16.16, fara semn (si -2^31 iese corect, 2^31)
+#[kernel]
+pub fn fixed_abs(a: Fixed32) -> b32 {
+    let mut m = a;
+    if a < signed(0) {
+        m = -a;
+    }
+    m.as_unsigned()
+}
+
+// impartire 16.16: (|a| << 16) / |b|, trunchiat spre 0
+// rezultatele care nu incap (inclusiv b = 0) sunt saturate la +-max
+#[kernel]
+pub fn fixed_div(a: Fixed32, b: Fixed32) -> Fixed32 {
+    let neg = (a < signed(0)) != (b < signed(0));
+    let n: b48 = fixed_abs(a).resize();
+    let d: b48 = fixed_abs(b).resize();
+
+    let q = udiv48(n << 16, d);
+
+    let mut mag: s32 = signed(0x7FFFFFFF);
+    if q <= bits(0x7FFFFFFF) {
+        let q32: b32 = q.resize();
+        mag = q32.as_signed();
+    }
+
+    let mut result = mag;
+    if neg {
+        result = -mag;
+    }
+    result
+}
+
+// 1 / b in 16.16 (b = 0 -> saturat)
+#[kernel]
+pub fn fixed_recip(b: Fixed32) -> Fixed32 {
+    fixed_div(signed(65536), b)
+}
+
+// radacina patrata 16.16: sqrt(a * 2^16 * 2^16) = sqrt(a) * 2^16, valorile negative dau 0
+#[kernel]
+pub fn fixed_sqrt(a: Fixed32) -> Fixed32 {
+    let mut root: s32 = signed(0);
+    if a > signed(0) {
+        let n: b64 = a.as_unsigned().resize();
+        let r: b32 = usqrt64(n << 16);
+        root = r.as_signed();
+    }
+    root
+}
+
+// 1 / sqrt(a) in 16.16, ca radacina urmata de impartire
+// radacina are eroare absoluta sub 2^-16, deci eroarea relativa creste pentru a mic
+#[kernel]
+pub fn fixed_rsqrt(a: Fixed32) -> Fixed32 {
+    fixed_recip(fixed_sqrt(a))
+}
+
+// lungimea unui vector, patratele raman pe 32.32 ca sa nu pierdem precizie si sa nu depasim la distante mari
+#[kernel]
+pub fn vector_norm(v: Vector3) -> Fixed32 {
+    let x: b64 = fixed_abs(v.x).resize();
+    let y: b64 = fixed_abs(v.y).resize();
+    let z: b64 = fixed_abs(v.z).resize();
+
+    // fiecare patrat e sub 2^62, suma incape fara semn in 64 biti
+    let r = usqrt64(x * x + y * y + z * z);
+
+    let mut norm: s32 = signed(0x7FFFFFFF);
+    if r <= bits(0x7FFFFFFF) {
+        norm = r.as_signed();
+    }
+    norm
+}
+
+// vectorul unitar (3 impartiri in paralel), vectorul nul ramane nul
+#[kernel]
+pub fn vector_normalize(v: Vector3) -> Vector3 {
+    let n = vector_norm(v);
+    let mut unit = v;
+    if n != signed(0) {
+        unit = Vector3 {
+            x: fixed_div(v.x, n),
+            y: fixed_div(v.y, n),
+            z: fixed_div(v.z, n),
+        };
+    }
+    unit
+}
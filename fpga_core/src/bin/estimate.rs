@@ -0,0 +1,110 @@
+use rhdl::prelude::*;
+use fpga_core::estimate::{estimate_kernel, print_table, register_bits, EstimateError, KernelEstimate};
+use fpga_core::iterative::{IterConfig, IterOp};
+use fpga_core::multiplier::MulPipeline;
+use fpga_core::projection::RangeImageConfig;
+use fpga_core::{alu, bist, bus, control_unit, engine, iterative, multiplier, perf, projection, scan, uart};
+
+// raport de resurse / timing pentru LidarProcessor si variantele lui
+// rulare: cargo run --bin estimate
+//
+// cifrele sunt estimari din RHIF, nu rezultate de sinteza; le folosim ca sa comparam alegeri de design
+
+fn core_rows() -> Result<Vec<KernelEstimate>, EstimateError> {
+    Ok(vec![
+        estimate_kernel::<control_unit::cu_kernel>("cu_kernel")?,
+        estimate_kernel::<engine::engine_kernel>("engine_kernel")?,
+        estimate_kernel::<perf::perf_kernel>("perf_kernel")?,
+        estimate_kernel::<bist::bist_kernel>("bist_kernel")?,
+        estimate_kernel::<scan::scan_kernel>("scan_kernel")?,
+        estimate_kernel::<fpga_core::top_kernel>("top_kernel")?,
+    ])
+}
+
+fn core_registers() -> usize {
+    register_bits::<control_unit::Q>()
+        + register_bits::<engine::Q>()
+        + register_bits::<perf::Q>()
+        + register_bits::<bist::Q>()
+        + register_bits::<scan::Q>()
+}
+
+fn run() -> Result<(), EstimateError> {
+    // 1. procesorul implicit (fara proiectie)
+    let core = core_rows()?;
+    print_table("=== LidarProcessor (implicit) ===", &core, core_registers());
+
+    // 2. cu etajul de range image activ: CORDIC-ul si registrul de iesire se adauga
+    let mut with_range = core.clone();
+    with_range.push(estimate_kernel::<projection::range_projector_kernel>("range_projector_kernel")?);
+    let projector_regs = register_bits::<projection::Q>() - <RangeImageConfig as Digital>::BITS;
+    print_table("=== LidarProcessor + range image ===", &with_range, core_registers() + projector_regs);
+
+    // 3. inmultirile: engine-ul face 9 inmultiri in paralel in CalcRot,
+    // iar DspMul e o singura inmultire 32x32 pipeline-uita (varianta partajata ar avea nevoie de 9 treceri)
+    let dsp_mul = estimate_kernel::<multiplier::dsp_mul_kernel>("dsp_mul_kernel")?;
+    let mul_regs = register_bits::<multiplier::Q>() - <MulPipeline as Digital>::BITS;
+    print_table("=== DspMul (registre complete; fara registre drumul e suma etajelor) ===",
+                std::slice::from_ref(&dsp_mul), mul_regs);
+    if let Some(engine_row) = core.iter().find(|r| r.name == "engine_kernel") {
+        println!("engine paralel: {} DSP, {:.2} ns | DspMul partajat: {} DSP, 9 treceri x {} cicluri",
+                 engine_row.dsp, engine_row.critical_path_ns, dsp_mul.dsp, MulPipeline::full().latency());
+        println!();
+    }
+
+    // 4. adaptoarele de transport
+    let bus_rows = vec![
+        estimate_kernel::<bus::deser32_kernel>("deser32_kernel")?,
+        estimate_kernel::<bus::ser32_kernel>("ser32_kernel")?,
+        estimate_kernel::<bus::bus_top_kernel>("bus_top_kernel")?,
+    ];
+    let bus_regs = register_bits::<bus::deser32::Q>() + register_bits::<bus::ser32::Q>();
+    print_table("=== Adaptor magistrala 32 biti (fara nucleu) ===", &bus_rows, bus_regs);
+
+    let uart_rows = vec![
+        estimate_kernel::<uart::uart_rx_kernel>("uart_rx_kernel")?,
+        estimate_kernel::<uart::uart_tx_kernel>("uart_tx_kernel")?,
+        estimate_kernel::<uart::frame_rx_kernel>("frame_rx_kernel")?,
+        estimate_kernel::<uart::frame_tx_kernel>("frame_tx_kernel")?,
+        estimate_kernel::<uart::uart_top_kernel>("uart_top_kernel")?,
+    ];
+    let uart_regs = register_bits::<uart::rx::Q>()
+        + register_bits::<uart::tx::Q>()
+        + register_bits::<uart::frame_rx::Q>()
+        + register_bits::<uart::frame_tx::Q>();
+    print_table("=== Legatura UART (fara nucleu) ===", &uart_rows, uart_regs);
+
+    // 5. impartire / radacina: desfasurate complet (un ciclu) fata de unitatea iterativa
+    let div_rows = vec![
+        estimate_kernel::<alu::fixed_div>("fixed_div")?,
+        estimate_kernel::<alu::fixed_sqrt>("fixed_sqrt")?,
+        estimate_kernel::<iterative::iterative_kernel>("iterative_kernel")?,
+    ];
+    let iter_regs = register_bits::<iterative::Q>() - <IterConfig as Digital>::BITS;
+    print_table("=== Impartire / radacina (combinational vs iterativ) ===", &div_rows, iter_regs);
+    for cfg in [IterConfig::full(), IterConfig::fast(), IterConfig::fast().with_frac_bits(8)] {
+        println!("IterativeUnit dublu {}, {} biti fractie: div {} cicluri, sqrt {} cicluri, recip {} cicluri, rsqrt {} cicluri",
+                 cfg.double_step, cfg.frac_bits.raw(), cfg.latency(IterOp::Div), cfg.latency(IterOp::Sqrt),
+                 cfg.latency(IterOp::Recip), cfg.latency(IterOp::Rsqrt));
+    }
+    println!();
+
+    Ok(())
+}
+
+fn main() {
+    match run() {
+        Ok(()) => {}
+        Err(EstimateError::Compile(e)) => {
+            let handler = miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor());
+            let mut msg = String::new();
+            handler.render_report(&mut msg, &e).unwrap();
+            println!("{}", msg);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            println!("estimate: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
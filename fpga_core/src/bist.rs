@@ -0,0 +1,208 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::dff::DFF;
+use crate::types::*;
+
+// built-in self test
+// cat timp bist_enable e activ, BIST-ul preia intrarea Engine-ului, trimite pe rand vectorii din ROM
+// si compara fiecare rezultat cu valoarea asteptata; la final ridica done + pass/fail
+// pe placa noua e suficient sa legam bist_enable la un buton si pass/fail la doua led-uri
+
+// numarul de vectori din ROM (indexul ultimului este BIST_VECTORS - 1)
+// kernelurile nu pot citi constanta, deci bist_rom si conditia de sfarsit din bist_kernel scriu indexul ca literal;
+// testbench-ul (run_bist_rom_test) verifica ca ROM-ul si automatul raman la BIST_VECTORS
+pub const BIST_VECTORS: usize = 10;
+
+// un vector de test: intrarea completa si rezultatul asteptat
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct BistVector {
+    pub point: Vector3,
+    pub rotation: Matrix3x3,
+    pub translation: Vector3,
+    pub expected: Vector3,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct BistInput {
+    pub enable: bool,
+    // rezultatul Engine-ului
+    pub result_valid: bool,
+    pub result: Vector3,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct BistOutput {
+    // cand e activ, top-ul trimite 'drive' in locul intrarii de la host
+    pub active: bool,
+    pub drive: LidarInput,
+    pub status: BistStatus,
+}
+
+#[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+pub struct Bist {
+    active: DFF<bool>,
+    index: DFF<b4>,
+    // vectorul curent a fost trimis, asteptam rezultatul
+    issued: DFF<bool>,
+    done: DFF<bool>,
+    fail: DFF<bool>,
+    fail_index: DFF<b4>,
+}
+
+impl Default for Bist {
+    fn default() -> Self {
+        Self {
+            active: DFF::new(false),
+            index: DFF::new(b4::default()),
+            issued: DFF::new(false),
+            done: DFF::new(false),
+            fail: DFF::new(false),
+            fail_index: DFF::new(b4::default()),
+        }
+    }
+}
+
+// interfata io
+impl SynchronousIO for Bist {
+    type I = BistInput;
+    type O = BistOutput;
+    type Kernel = bist_kernel;
+}
+
+#[kernel]
+pub fn vec3(x: Fixed32, y: Fixed32, z: Fixed32) -> Vector3 {
+    Vector3 { x, y, z }
+}
+
+// ROM-ul cu vectorii de test (valorile in 16.16 si Q2.16, scrise ca intregi)
+// toate rezultatele sunt exacte, deci comparatia e pe egalitate
+#[kernel]
+pub fn bist_rom(index: b4) -> BistVector {
+    let zero: RotFixed = signed(0);
+    let one: RotFixed = signed(65536); // 1.0 in Q2.16
+    let neg_one: RotFixed = signed(-65536);
+
+    let identity = Matrix3x3 { rows: [[one, zero, zero], [zero, one, zero], [zero, zero, one]] };
+    let rot_x90 = Matrix3x3 { rows: [[one, zero, zero], [zero, zero, neg_one], [zero, one, zero]] };
+    let rot_y90 = Matrix3x3 { rows: [[zero, zero, one], [zero, one, zero], [neg_one, zero, zero]] };
+    let rot_z90 = Matrix3x3 { rows: [[zero, neg_one, zero], [one, zero, zero], [zero, zero, one]] };
+
+    // P(1, 2, 3) folosit de vectorii de rotatie
+    let p123 = vec3(signed(65536), signed(131072), signed(196608));
+    let no_trans = Vector3::default();
+
+    let mut v = BistVector {
+        point: p123,
+        rotation: identity,
+        translation: no_trans,
+        expected: p123,
+    };
+
+    if index == bits(1) {
+        // rotatie 90 grade pe X: (1, 2, 3) -> (1, -3, 2)
+        v.rotation = rot_x90;
+        v.expected = vec3(signed(65536), signed(-196608), signed(131072));
+    } else if index == bits(2) {
+        // rotatie 90 grade pe Y: (1, 2, 3) -> (3, 2, -1)
+        v.rotation = rot_y90;
+        v.expected = vec3(signed(196608), signed(131072), signed(-65536));
+    } else if index == bits(3) {
+        // rotatie 90 grade pe Z: (1, 2, 3) -> (-2, 1, 3)
+        v.rotation = rot_z90;
+        v.expected = vec3(signed(-131072), signed(65536), signed(196608));
+    } else if index == bits(4) {
+        // translatie pura: (1, 2, 3) + (10, -20, 0.5) = (11, -18, 3.5)
+        v.translation = vec3(signed(655360), signed(-1310720), signed(32768));
+        v.expected = vec3(signed(720896), signed(-1179648), signed(229376));
+    } else if index == bits(5) {
+        // capetele domeniului de punct (+-256 m, vezi point_slice): identitatea trebuie sa le pastreze
+        v.point = vec3(signed(0xFF_FFFF), signed(-0x100_0000), signed(0x7F_0000));
+        v.expected = v.point;
+    } else if index == bits(6) {
+        // rotatie Z pe un punct de la margine: (-256, 255, 1) -> (-255, -256, 1)
+        v.point = vec3(signed(-0x100_0000), signed(0xFF_0000), signed(65536));
+        v.rotation = rot_z90;
+        v.expected = vec3(signed(-0xFF_0000), signed(-0x100_0000), signed(65536));
+    } else if index == bits(7) {
+        // valori fractionare, pana la 1 LSB: (0.5, -0.25, 2^-16) + (-0.5, 0.25, 0) = (0, 0, 2^-16)
+        v.point = vec3(signed(32768), signed(-16384), signed(1));
+        v.translation = vec3(signed(-32768), signed(16384), signed(0));
+        v.expected = vec3(signed(0), signed(0), signed(1));
+    } else if index == bits(8) {
+        // saturare la translatie: (255, -255, 0) + (32700, -32700, 0.5) iese din 16.16
+        // x si y raman la capete (max / min), z = 0.5 nu e afectat
+        v.point = vec3(signed(0xFF_0000), signed(-0xFF_0000), signed(0));
+        v.translation = vec3(signed(2_143_027_200), signed(-2_143_027_200), signed(32768));
+        v.expected = vec3(signed(0x7FFF_FFFF), signed(-0x8000_0000), signed(32768));
+    } else if index == bits(9) {
+        // punct dincolo de 256 m: coordonatele sunt saturate la marginea portului DSP inainte de rotatie
+        // (1000, -1000, 1) -> (256 - 2^-16, -256, 1)
+        v.point = vec3(signed(65_536_000), signed(-65_536_000), signed(65536));
+        v.expected = vec3(signed(0xFF_FFFF), signed(-0x100_0000), signed(65536));
+    }
+
+    v
+}
+
+#[kernel]
+pub fn bist_kernel(_cr: ClockReset, input: BistInput, q: Q) -> (BistOutput, D) {
+    let vector = bist_rom(q.index);
+
+    // intrarea trimisa catre automat si Engine
+    // pose-ul se scrie si se activeaza odata cu punctul, ca la host
+    let mut drive = LidarInput::default();
+    drive.point = vector.point;
+    drive.rotation = vector.rotation;
+    drive.translation = vector.translation;
+    if q.active && !q.issued {
+        drive.valid = true;
+        drive.pose_write = true;
+        drive.pose_swap = true;
+    }
+
+    let mut d = D {
+        active: q.active,
+        index: q.index,
+        issued: q.issued,
+        done: q.done,
+        fail: q.fail,
+        fail_index: q.fail_index,
+    };
+
+    if !input.enable {
+        // testul se reia de la inceput la urmatoarea activare
+        d.active = false;
+        d.index = bits(0);
+        d.issued = false;
+        d.done = false;
+        d.fail = false;
+        d.fail_index = bits(0);
+    } else if !q.active && !q.done {
+        d.active = true;
+    } else if q.active && !q.issued {
+        d.issued = true;
+    } else if q.active && input.result_valid {
+        // comparam rezultatul si trecem la urmatorul vector
+        if input.result != vector.expected && !q.fail {
+            d.fail = true;
+            d.fail_index = q.index;
+        }
+        d.issued = false;
+        // ultimul vector: BIST_VECTORS - 1
+        if q.index == bits(9) {
+            d.active = false;
+            d.done = true;
+        } else {
+            d.index = q.index + bits(1);
+        }
+    }
+
+    let status = BistStatus {
+        running: q.active,
+        done: q.done,
+        pass: q.done && !q.fail,
+        fail: q.done && q.fail,
+        fail_index: q.fail_index,
+    };
+
+    (BistOutput { active: q.active, drive, status }, d)
+}
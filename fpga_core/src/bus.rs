@@ -0,0 +1,524 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::dff::DFF;
+use crate::types::*;
+use crate::LidarProcessor;
+
+// adaptoare pentru magistrale inguste (32 sau 64 biti)
+// LidarInput are 16 cuvinte de 32 biti, LidarOutput are 4
+//
+// ordinea cuvintelor de intrare (aceeasi si in host_software/src/bus_packer.rs):
+//   0       header: bit 0 valid, bit 1 pose_write, bit 2 pose_swap, bit 3 bist_enable,
+//           bit 4 first, bit 5 last, bit 6 pose_compose,
+//           bitii 7..=8 debug_sel (0 final, 1 raw, 2 rotated), bit 9 velocity_mode,
+//           bitii 10..=11 ctx_id, bitii 12..=13 pose_ctx
+//   1..=3   point x, y, z (16.16)
+//   4..=12  rotation, pe linii: r00 r01 r02 r10 ... r22 (Q2.16 extins cu semn la 32 biti)
+//   13..=15 translation x, y, z (16.16)
+// cu velocity_mode, cuvintele 4..=10 poarta omega x, y, z, velocity x, y, z si dt (16.16) in locul rotatiei
+//
+// ordinea cuvintelor de iesire:
+//   0       header: bit 0 valid, bit 1 first, bit 2 last, bit 3 scan_done, bitii 4..=5 ctx_id,
+//           bit 6 compact, bit 7 compact pe 32 biti, bit 11 compact in cm (altfel mm),
+//           bit 8 bist done, bit 9 bist pass, bit 10 bist fail,
+//           bitii 12..=31 scan_count (trunchiat la 20 de biti, pana la ~1M puncte pe scanare)
+//   1..=3   corrected_point x, y, z (16.16)
+//           cu bit 6 activ: cuvintele 1..=3 sunt bitii 0..=95 din CompactPoint.data (vezi format.rs)
+//
+// pe magistrala de 64 biti, beat-ul k contine cuvantul 2k in partea de jos si 2k+1 in partea de sus
+// primul cuvant al unui pachet vine cu 'sof' activ, ca receptorul sa se poata resincroniza
+pub const INPUT_WORDS: usize = 16;
+pub const OUTPUT_WORDS: usize = 4;
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct BusWord32 {
+    pub valid: bool,
+    pub sof: bool, // start of frame, primul cuvant din pachet
+    pub data: b32,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct BusWord64 {
+    pub valid: bool,
+    pub sof: bool,
+    pub data: b64,
+}
+
+// scrie cuvantul 'index' in intrarea partial asamblata
+#[kernel]
+pub fn assemble_word(acc: LidarInput, index: b5, word: b32) -> LidarInput {
+    let mut next = acc;
+    let value: s32 = word.as_signed();
+    let rot: RotFixed = value.resize();
+
+    if index == bits(0) {
+        next.valid = (word & bits(1)) != bits(0);
+        next.pose_write = (word & bits(2)) != bits(0);
+        next.pose_swap = (word & bits(4)) != bits(0);
+        next.bist_enable = (word & bits(8)) != bits(0);
+        next.first = (word & bits(16)) != bits(0);
+        next.last = (word & bits(32)) != bits(0);
+        next.pose_compose = (word & bits(64)) != bits(0);
+        next.velocity_mode = (word & bits(0x200)) != bits(0);
+        next.ctx_id = (word >> 10).resize();
+        next.pose_ctx = (word >> 12).resize();
+        let sel: b2 = (word >> 7).resize();
+        next.debug_sel = DebugSel::Final;
+        if sel == bits(1) {
+            next.debug_sel = DebugSel::Raw;
+        } else if sel == bits(2) {
+            next.debug_sel = DebugSel::Rotated;
+        }
+    } else if index == bits(1) {
+        next.point.x = value;
+    } else if index == bits(2) {
+        next.point.y = value;
+    } else if index == bits(3) {
+        next.point.z = value;
+    } else if index == bits(4) {
+        next.rotation.rows[0][0] = rot;
+    } else if index == bits(5) {
+        next.rotation.rows[0][1] = rot;
+    } else if index == bits(6) {
+        next.rotation.rows[0][2] = rot;
+    } else if index == bits(7) {
+        next.rotation.rows[1][0] = rot;
+    } else if index == bits(8) {
+        next.rotation.rows[1][1] = rot;
+    } else if index == bits(9) {
+        next.rotation.rows[1][2] = rot;
+    } else if index == bits(10) {
+        next.rotation.rows[2][0] = rot;
+    } else if index == bits(11) {
+        next.rotation.rows[2][1] = rot;
+    } else if index == bits(12) {
+        next.rotation.rows[2][2] = rot;
+    } else if index == bits(13) {
+        next.translation.x = value;
+    } else if index == bits(14) {
+        next.translation.y = value;
+    } else if index == bits(15) {
+        next.translation.z = value;
+    }
+
+    // header-ul (cuvantul 0) vine primul, deci acc.velocity_mode e deja cel al pachetului curent
+    if acc.velocity_mode {
+        if index == bits(4) {
+            next.omega.x = value;
+        } else if index == bits(5) {
+            next.omega.y = value;
+        } else if index == bits(6) {
+            next.omega.z = value;
+        } else if index == bits(7) {
+            next.velocity.x = value;
+        } else if index == bits(8) {
+            next.velocity.y = value;
+        } else if index == bits(9) {
+            next.velocity.z = value;
+        } else if index == bits(10) {
+            next.dt = value;
+        }
+    }
+
+    next
+}
+
+// cuvantul 'index' dintr-o intrare completa (inversul lui assemble_word, folosit in testbench)
+#[kernel]
+pub fn input_word(input: LidarInput, index: b5) -> b32 {
+    let mut value: s32 = signed(0);
+
+    if index == bits(0) {
+        let mut header: b32 = bits(0);
+        if input.valid { header = header | bits(1); }
+        if input.pose_write { header = header | bits(2); }
+        if input.pose_swap { header = header | bits(4); }
+        if input.bist_enable { header = header | bits(8); }
+        if input.first { header = header | bits(16); }
+        if input.last { header = header | bits(32); }
+        if input.pose_compose { header = header | bits(64); }
+        match input.debug_sel {
+            DebugSel::Final => {},
+            DebugSel::Raw => { header = header | bits(0x80); },
+            DebugSel::Rotated => { header = header | bits(0x100); },
+        }
+        if input.velocity_mode { header = header | bits(0x200); }
+        let ctx_id: b32 = input.ctx_id.resize();
+        let pose_ctx: b32 = input.pose_ctx.resize();
+        header = header | (ctx_id << 10) | (pose_ctx << 12);
+        value = header.as_signed();
+    } else if index == bits(1) {
+        value = input.point.x;
+    } else if index == bits(2) {
+        value = input.point.y;
+    } else if index == bits(3) {
+        value = input.point.z;
+    } else if index == bits(4) {
+        value = input.rotation.rows[0][0].resize();
+    } else if index == bits(5) {
+        value = input.rotation.rows[0][1].resize();
+    } else if index == bits(6) {
+        value = input.rotation.rows[0][2].resize();
+    } else if index == bits(7) {
+        value = input.rotation.rows[1][0].resize();
+    } else if index == bits(8) {
+        value = input.rotation.rows[1][1].resize();
+    } else if index == bits(9) {
+        value = input.rotation.rows[1][2].resize();
+    } else if index == bits(10) {
+        value = input.rotation.rows[2][0].resize();
+    } else if index == bits(11) {
+        value = input.rotation.rows[2][1].resize();
+    } else if index == bits(12) {
+        value = input.rotation.rows[2][2].resize();
+    } else if index == bits(13) {
+        value = input.translation.x;
+    } else if index == bits(14) {
+        value = input.translation.y;
+    } else if index == bits(15) {
+        value = input.translation.z;
+    }
+
+    if input.velocity_mode {
+        if index == bits(4) {
+            value = input.omega.x;
+        } else if index == bits(5) {
+            value = input.omega.y;
+        } else if index == bits(6) {
+            value = input.omega.z;
+        } else if index == bits(7) {
+            value = input.velocity.x;
+        } else if index == bits(8) {
+            value = input.velocity.y;
+        } else if index == bits(9) {
+            value = input.velocity.z;
+        } else if index == bits(10) {
+            value = input.dt;
+        }
+    }
+
+    value.as_unsigned()
+}
+
+// cuvantul 'index' din iesire
+#[kernel]
+pub fn output_word(output: LidarOutput, index: b3) -> b32 {
+    let mut word: b32 = bits(0);
+
+    if index == bits(0) {
+        if output.valid { word = word | bits(1); }
+        if output.first { word = word | bits(2); }
+        if output.last { word = word | bits(4); }
+        if output.scan_done { word = word | bits(8); }
+        let ctx_id: b32 = output.ctx_id.resize();
+        word = word | (ctx_id << 4);
+        word = word | (output.scan_count << 12);
+        if output.compact.valid { word = word | bits(0x40); }
+        if output.compact.wide { word = word | bits(0x80); }
+        match output.compact.unit {
+            CompactUnit::Millimetre => {},
+            CompactUnit::Centimetre => { word = word | bits(0x800); },
+        }
+        if output.bist.done { word = word | bits(0x100); }
+        if output.bist.pass { word = word | bits(0x200); }
+        if output.bist.fail { word = word | bits(0x400); }
+    } else if index == bits(1) {
+        word = output.corrected_point.x.as_unsigned();
+    } else if index == bits(2) {
+        word = output.corrected_point.y.as_unsigned();
+    } else if index == bits(3) {
+        word = output.corrected_point.z.as_unsigned();
+    }
+
+    if output.compact.valid {
+        if index == bits(1) {
+            word = output.compact.data.resize();
+        } else if index == bits(2) {
+            word = (output.compact.data >> 32).resize();
+        } else if index == bits(3) {
+            word = (output.compact.data >> 64).resize();
+        }
+    }
+
+    word
+}
+
+// iesirea unui deserializator: datele raman tinute dupa pachet (automatul le citeste in Load),
+// dar comenzile sunt active doar in ciclul in care pachetul s-a terminat
+// first/last sunt date ale punctului, se citesc tot in Load, deci raman tinute
+#[kernel]
+pub fn hold_data(input: LidarInput) -> LidarInput {
+    let mut held = input;
+    held.valid = false;
+    held.pose_write = false;
+    held.pose_swap = false;
+    held.pose_compose = false;
+    held
+}
+
+// fiecare componenta sta in submodulul ei: SynchronousDQ genereaza tipurile Q si D la nivelul modulului
+// submodulele sunt re-exportate, deci caile (bus::WordSerializer32 etc.) raman aceleasi
+
+// deserializator 32 biti: 16 cuvinte -> LidarInput
+pub mod deser32 {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct WordDeserializer32 {
+        index: DFF<b5>,
+        acc: DFF<LidarInput>,
+    }
+
+    impl Default for WordDeserializer32 {
+        fn default() -> Self {
+            Self {
+                index: DFF::new(b5::default()),
+                acc: DFF::new(LidarInput::default()),
+            }
+        }
+    }
+
+    impl SynchronousIO for WordDeserializer32 {
+        type I = BusWord32;
+        type O = LidarInput;
+        type Kernel = deser32_kernel;
+    }
+
+    #[kernel]
+    pub fn deser32_kernel(_cr: ClockReset, word: BusWord32, q: Q) -> (LidarInput, D) {
+        let mut output = hold_data(q.acc);
+        let mut d = D { index: q.index, acc: q.acc };
+
+        if word.valid {
+            // sof forteaza inceputul unui pachet nou
+            let mut index = q.index;
+            if word.sof {
+                index = bits(0);
+            }
+
+            let assembled = assemble_word(q.acc, index, word.data);
+            if index == bits(15) {
+                output = assembled;
+                d.acc = hold_data(assembled);
+                d.index = bits(0);
+            } else {
+                d.acc = assembled;
+                d.index = index + bits(1);
+            }
+        }
+
+        (output, d)
+    }
+}
+pub use deser32::{WordDeserializer32, deser32_kernel};
+
+// deserializator 64 biti: 8 beat-uri -> LidarInput
+pub mod deser64 {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct WordDeserializer64 {
+        beat: DFF<b4>,
+        acc: DFF<LidarInput>,
+    }
+
+    impl Default for WordDeserializer64 {
+        fn default() -> Self {
+            Self {
+                beat: DFF::new(b4::default()),
+                acc: DFF::new(LidarInput::default()),
+            }
+        }
+    }
+
+    impl SynchronousIO for WordDeserializer64 {
+        type I = BusWord64;
+        type O = LidarInput;
+        type Kernel = deser64_kernel;
+    }
+
+    #[kernel]
+    pub fn deser64_kernel(_cr: ClockReset, word: BusWord64, q: Q) -> (LidarInput, D) {
+        let mut output = hold_data(q.acc);
+        let mut d = D { beat: q.beat, acc: q.acc };
+
+        if word.valid {
+            let mut beat = q.beat;
+            if word.sof {
+                beat = bits(0);
+            }
+
+            // doua cuvinte pe beat: 2k jos, 2k+1 sus
+            let beat_wide: b5 = beat.resize();
+            let index_lo = beat_wide << 1;
+            let index_hi = index_lo + bits(1);
+            let lo: b32 = word.data.resize();
+            let hi: b32 = (word.data >> 32).resize();
+
+            let assembled = assemble_word(assemble_word(q.acc, index_lo, lo), index_hi, hi);
+            if beat == bits(7) {
+                output = assembled;
+                d.acc = hold_data(assembled);
+                d.beat = bits(0);
+            } else {
+                d.acc = assembled;
+                d.beat = beat + bits(1);
+            }
+        }
+
+        (output, d)
+    }
+}
+pub use deser64::{WordDeserializer64, deser64_kernel};
+
+// serializator 32 biti: LidarOutput -> 4 cuvinte
+// automatul emite cel mult un punct la 4 cicluri, deci un pachet se termina inainte sa vina urmatorul
+pub mod ser32 {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct WordSerializer32 {
+        data: DFF<LidarOutput>,
+        index: DFF<b3>,
+        busy: DFF<bool>,
+    }
+
+    impl Default for WordSerializer32 {
+        fn default() -> Self {
+            Self {
+                data: DFF::new(LidarOutput::default()),
+                index: DFF::new(b3::default()),
+                busy: DFF::new(false),
+            }
+        }
+    }
+
+    impl SynchronousIO for WordSerializer32 {
+        type I = LidarOutput;
+        type O = BusWord32;
+        type Kernel = ser32_kernel;
+    }
+
+    #[kernel]
+    pub fn ser32_kernel(_cr: ClockReset, input: LidarOutput, q: Q) -> (BusWord32, D) {
+        let output = BusWord32 {
+            valid: q.busy,
+            sof: q.busy && q.index == bits(0),
+            data: output_word(q.data, q.index),
+        };
+
+        let mut d = D { data: q.data, index: q.index, busy: q.busy };
+        if q.busy {
+            if q.index == bits(3) {
+                d.busy = false;
+            }
+            d.index = q.index + bits(1);
+        }
+
+        // un punct nou incepe un pachet nou
+        if input.valid {
+            d.data = input;
+            d.index = bits(0);
+            d.busy = true;
+        }
+
+        (output, d)
+    }
+}
+pub use ser32::{WordSerializer32, ser32_kernel};
+
+// serializator 64 biti: LidarOutput -> 2 beat-uri
+pub mod ser64 {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct WordSerializer64 {
+        data: DFF<LidarOutput>,
+        beat: DFF<b2>,
+        busy: DFF<bool>,
+    }
+
+    impl Default for WordSerializer64 {
+        fn default() -> Self {
+            Self {
+                data: DFF::new(LidarOutput::default()),
+                beat: DFF::new(b2::default()),
+                busy: DFF::new(false),
+            }
+        }
+    }
+
+    impl SynchronousIO for WordSerializer64 {
+        type I = LidarOutput;
+        type O = BusWord64;
+        type Kernel = ser64_kernel;
+    }
+
+    #[kernel]
+    pub fn ser64_kernel(_cr: ClockReset, input: LidarOutput, q: Q) -> (BusWord64, D) {
+        let beat_wide: b3 = q.beat.resize();
+        let index_lo = beat_wide << 1;
+        let lo: b64 = output_word(q.data, index_lo).resize();
+        let hi: b64 = output_word(q.data, index_lo + bits(1)).resize();
+
+        let output = BusWord64 {
+            valid: q.busy,
+            sof: q.busy && q.beat == bits(0),
+            data: (hi << 32) | lo,
+        };
+
+        let mut d = D { data: q.data, beat: q.beat, busy: q.busy };
+        if q.busy {
+            if q.beat == bits(1) {
+                d.busy = false;
+            }
+            d.beat = q.beat + bits(1);
+        }
+
+        if input.valid {
+            d.data = input;
+            d.beat = bits(0);
+            d.busy = true;
+        }
+
+        (output, d)
+    }
+}
+pub use ser64::{WordSerializer64, ser64_kernel};
+
+// procesorul complet legat la o magistrala de 32 biti
+pub mod top {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct BusLidarProcessor {
+        rx: WordDeserializer32,
+        core: LidarProcessor,
+        tx: WordSerializer32,
+    }
+
+    impl Default for BusLidarProcessor {
+        fn default() -> Self {
+            Self {
+                rx: WordDeserializer32::default(),
+                core: LidarProcessor::default(),
+                tx: WordSerializer32::default(),
+            }
+        }
+    }
+
+    impl SynchronousIO for BusLidarProcessor {
+        type I = BusWord32;
+        type O = BusWord32;
+        type Kernel = bus_top_kernel;
+    }
+
+    #[kernel]
+    pub fn bus_top_kernel(_cr: ClockReset, word: BusWord32, q: Q) -> (BusWord32, D) {
+        let mut d = D::dont_care();
+        d.rx = word;
+        d.core = q.rx;
+        d.tx = q.core;
+        (q.tx, d)
+    }
+}
+pub use top::{BusLidarProcessor, bus_top_kernel};
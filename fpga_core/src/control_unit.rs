@@ -7,6 +7,7 @@ pub struct ControlSignals {
     pub load_input: bool,   // permite scrierea datelor de intrare in registre
     pub save_temp: bool,    // permite salvarea rezultatului intermediar (rotatia)
     pub output_valid: bool, // semnalizeaza ca rezultatul final e gata
+    pub busy: bool,         // automatul proceseaza un punct si nu poate accepta altul
 }
 
 // starile automatului finit
@@ -53,14 +54,17 @@ pub fn cu_kernel(_cr: ClockReset, valid_in: bool, q: Q) -> (ControlSignals, D) {
             }
         },
         State::Load => {
+            cs.busy = true;
             cs.load_input = true;
             next_state = State::CalcRot;
         },
         State::CalcRot => {
+            cs.busy = true;
             cs.save_temp = true;
             next_state = State::CalcTrans;
         },
         State::CalcTrans => {
+            cs.busy = true;
             cs.output_valid = true;
             next_state = State::Idle;
         }
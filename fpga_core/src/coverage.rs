@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rhdl::prelude::*;
+
+// acoperirea simularilor: starile si tranzitiile automatului din control_unit + toggle pe registrele din engine
+//
+// nu adaugam porturi de debug in hardware: simularea e parcursa o singura data sub .collect::<Vcd>() (traced_run),
+// iar semnalele interne sunt citite din dump-ul VCD (aceleasi pe care le vedem in GTKWave), scris in memorie
+//   - automatul: semnalele 'state' din orice scope 'cu' (merge si pentru procesorul din bus.rs / uart.rs)
+//   - toggle: toate semnalele din scope-urile 'engine'; un bit e acoperit daca a trecut si 0 -> 1 si 1 -> 0
+// acoperirea se aduna peste oricate rulari
+
+// ordinea starilor din control_unit::State (discriminantul din codificarea RHDL)
+pub const FSM_STATES: [&str; 4] = ["Idle", "Load", "CalcRot", "CalcTrans"];
+
+// tranzitiile posibile din cu_kernel (fara ramanerea in Idle)
+pub const FSM_TRANSITIONS: [(usize, usize); 4] = [(0, 1), (1, 2), (2, 3), (3, 0)];
+
+// parcurge o simulare o singura data: semnalele interne ajung in VCD doar cat timp simularea ruleaza
+// sub .collect::<Vcd>(), asa ca esantioanele sunt copiate pe drum si raman la apelant
+pub fn traced_run<T: Digital>(run: impl Iterator<Item = TimedSample<T>>) -> (Vec<TimedSample<T>>, Vcd) {
+    let mut samples = Vec::new();
+    let vcd = run.inspect(|sample| samples.push(sample.clone())).collect::<Vcd>();
+    (samples, vcd)
+}
+
+// istoria unui bit pentru toggle coverage
+#[derive(Debug, Default, Clone, Copy)]
+struct BitToggle {
+    last: Option<bool>,
+    rose: bool,
+    fell: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Coverage {
+    runs: usize,
+    states: BTreeSet<usize>,
+    transitions: BTreeSet<(usize, usize)>,
+    // tranzitii care nu exista in cu_kernel (n-ar trebui sa apara niciodata)
+    illegal: BTreeSet<(usize, usize)>,
+    // registru engine -> bitii lui
+    toggles: BTreeMap<String, Vec<BitToggle>>,
+}
+
+// o variabila din antetul VCD
+struct VcdVar {
+    path: Vec<String>,
+    width: usize,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // adauga acoperirea unei rulari (vezi traced_run); dump-ul se face intr-un buffer, nu intr-un fisier,
+    // deci workerii care simuleaza in paralel nu se incurca
+    pub fn add_run(&mut self, vcd: Vcd) -> anyhow::Result<()> {
+        let mut buffer = Vec::new();
+        vcd.dump(&mut buffer)?;
+        self.add_vcd(&String::from_utf8(buffer)?);
+        Ok(())
+    }
+
+    // adauga acoperirea dintr-un dump VCD
+    pub fn add_vcd(&mut self, text: &str) {
+        self.runs += 1;
+
+        let mut scope: Vec<String> = Vec::new();
+        let mut vars: BTreeMap<String, Vec<VcdVar>> = BTreeMap::new();
+        let mut last_state: BTreeMap<String, usize> = BTreeMap::new();
+        let mut tokens = text.split_whitespace();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "$scope" => {
+                    tokens.next(); // tipul (module)
+                    if let Some(name) = tokens.next() {
+                        scope.push(name.to_string());
+                    }
+                    skip_to_end(&mut tokens);
+                }
+                "$upscope" => {
+                    scope.pop();
+                    skip_to_end(&mut tokens);
+                }
+                "$var" => {
+                    tokens.next(); // tipul (wire, reg, string)
+                    let width = tokens.next().and_then(|w| w.parse().ok()).unwrap_or(1);
+                    let id = tokens.next().unwrap_or_default().to_string();
+                    let name = tokens.next().unwrap_or_default().to_string();
+                    skip_to_end(&mut tokens);
+
+                    let mut path = scope.clone();
+                    path.push(name);
+                    vars.entry(id).or_default().push(VcdVar { path, width });
+                }
+                // sectiunile fara valori
+                "$timescale" | "$date" | "$version" | "$comment" | "$enddefinitions" => {
+                    skip_to_end(&mut tokens);
+                }
+                // marcajele de timp si $dumpvars/$end nu ne intereseaza, doar valorile
+                _ if token.starts_with('#') || token.starts_with('$') => {}
+                _ => {
+                    // valoare vectoriala ("b0101 id"), string ("sIdle id") sau scalara ("1id")
+                    let (value, id) = match token.as_bytes()[0] {
+                        b'b' | b'B' | b's' | b'S' | b'r' | b'R' => {
+                            (token.to_string(), tokens.next().unwrap_or_default().to_string())
+                        }
+                        _ => (format!("b{}", &token[..1]), token[1..].to_string()),
+                    };
+                    if let Some(list) = vars.get(&id) {
+                        for var in list {
+                            self.observe(var, &id, &value, &mut last_state);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn observe(&mut self, var: &VcdVar, id: &str, value: &str, last_state: &mut BTreeMap<String, usize>) {
+        let in_scope = |name: &str| var.path[..var.path.len() - 1].iter().any(|s| s == name);
+
+        // automatul
+        if in_scope("cu") && var.path.iter().any(|s| s.contains("state")) {
+            if let Some(state) = decode_state(value) {
+                self.states.insert(state);
+                if let Some(&prev) = last_state.get(id) {
+                    if prev != state {
+                        if FSM_TRANSITIONS.contains(&(prev, state)) {
+                            self.transitions.insert((prev, state));
+                        } else {
+                            self.illegal.insert((prev, state));
+                        }
+                    }
+                }
+                last_state.insert(id.to_string(), state);
+            }
+        }
+
+        // registrele din engine (doar valorile pe biti)
+        if in_scope("engine") && value.starts_with(['b', 'B']) {
+            let name = var.path.iter()
+                .skip_while(|s| s.as_str() != "engine")
+                .skip(1)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(".");
+            let bits = self.toggles.entry(name).or_insert_with(|| vec![BitToggle::default(); var.width]);
+
+            // valorile mai scurte decat variabila sunt completate cu 0 la stanga
+            let digits = &value[1..];
+            for (k, bit) in bits.iter_mut().enumerate() {
+                let c = if k < digits.len() { digits.as_bytes()[digits.len() - 1 - k] } else { b'0' };
+                let level = match c {
+                    b'0' => false,
+                    b'1' => true,
+                    _ => continue, // x / z
+                };
+                match bit.last {
+                    Some(false) if level => bit.rose = true,
+                    Some(true) if !level => bit.fell = true,
+                    _ => {}
+                }
+                bit.last = Some(level);
+            }
+        }
+    }
+
+    // adauga acoperirea colectata separat (ex: de alt fir de simulare din host)
+    pub fn merge(&mut self, other: &Coverage) {
+        self.runs += other.runs;
+        self.states.extend(&other.states);
+        self.transitions.extend(&other.transitions);
+        self.illegal.extend(&other.illegal);
+        for (name, bits) in &other.toggles {
+            let mine = self.toggles.entry(name.clone()).or_insert_with(|| vec![BitToggle::default(); bits.len()]);
+            for (bit, theirs) in mine.iter_mut().zip(bits) {
+                bit.rose |= theirs.rose;
+                bit.fell |= theirs.fell;
+            }
+        }
+    }
+
+    // bitii acoperiti si totalul, peste toate registrele din engine
+    pub fn toggle_totals(&self) -> (usize, usize) {
+        let covered = self.toggles.values().flatten().filter(|b| b.rose && b.fell).count();
+        let total = self.toggles.values().map(|b| b.len()).sum();
+        (covered, total)
+    }
+
+    pub fn print_summary(&self) {
+        println!("Acoperire ({} rulari):", self.runs);
+
+        let states: Vec<&str> = self.states.iter().filter_map(|&s| FSM_STATES.get(s).copied()).collect();
+        println!("  Stari automat:    {} / {} {:?}", self.states.len(), FSM_STATES.len(), states);
+
+        let missing: Vec<String> = FSM_TRANSITIONS.iter()
+            .filter(|t| !self.transitions.contains(t))
+            .map(|&(a, b)| format!("{} -> {}", FSM_STATES[a], FSM_STATES[b]))
+            .collect();
+        println!("  Tranzitii:        {} / {} (lipsa: {:?})", self.transitions.len(), FSM_TRANSITIONS.len(), missing);
+        if !self.illegal.is_empty() {
+            println!("  ATENTIE: tranzitii neasteptate {:?}", self.illegal);
+        }
+
+        let (covered, total) = self.toggle_totals();
+        let percent = if total > 0 { covered as f64 / total as f64 * 100.0 } else { 0.0 };
+        println!("  Toggle engine:    {} / {} biti ({:.1}%)", covered, total, percent);
+        for (name, bits) in &self.toggles {
+            let untoggled = bits.iter().filter(|b| !(b.rose && b.fell)).count();
+            if untoggled > 0 {
+                println!("    {:<40} {} / {} biti fara toggle", name, untoggled, bits.len());
+            }
+        }
+    }
+}
+
+fn skip_to_end<'a>(tokens: &mut impl Iterator<Item = &'a str>) {
+    for t in tokens.by_ref() {
+        if t == "$end" {
+            break;
+        }
+    }
+}
+
+// starea automatului din valoarea VCD: discriminantul pe biti sau numele variantei
+fn decode_state(value: &str) -> Option<usize> {
+    let (kind, body) = value.split_at(1);
+    match kind {
+        "b" | "B" => usize::from_str_radix(body, 2).ok().filter(|&s| s < FSM_STATES.len()),
+        "s" | "S" => FSM_STATES.iter().position(|name| body.contains(name)),
+        _ => None,
+    }
+}
@@ -10,8 +10,23 @@ use crate::control_unit::ControlSignals;
 pub struct Engine {
     // registre intrare
     pub point: DFF<Vector3>,
-    pub rotation: DFF<Matrix3x3>,
-    pub translation: DFF<Vector3>,
+    // pose-ul [R | t] al punctului din calcul, copiat din contextul ales la Load
+    pub pose: DFF<Matrix4x4>,
+    // pose-urile active, cate unul pe context
+    pub contexts: DFF<PoseContexts>,
+
+    // registre shadow: urmatorul pose al fiecarui context se scrie (sau se compune) aici cat timp cel activ e folosit
+    pub shadow_pose: DFF<PoseContexts>,
+    // swap cerut dar inca neaplicat, pe context
+    pub swap_pending: DFF<[bool; POSE_CONTEXTS]>,
+    // contextul punctului din calcul
+    pub ctx_id: DFF<b2>,
+
+    // marcajele de scanare ale punctului din calcul
+    pub first: DFF<bool>,
+    pub last: DFF<bool>,
+    // ce trimitem la iesire (punct corectat, brut sau doar rotit)
+    pub debug_sel: DFF<DebugSel>,
 
     // registru intermediar (scoate rezultatul rotatiei ie P_rot = R * P)
     pub temp_rotated: DFF<Vector3>,
@@ -21,13 +36,39 @@ impl Default for Engine {
     fn default() -> Self {
         Self {
             point: DFF::new(Vector3::default()),
-            rotation: DFF::new(Matrix3x3::default()),
-            translation: DFF::new(Vector3::default()),
+            pose: DFF::new(Matrix4x4::default()),
+            contexts: DFF::new(PoseContexts::default()),
+            shadow_pose: DFF::new(PoseContexts::default()),
+            swap_pending: DFF::new([false; POSE_CONTEXTS]),
+            ctx_id: DFF::new(bits(0)),
+            first: DFF::new(false),
+            last: DFF::new(false),
+            debug_sel: DFF::new(DebugSel::Final),
             temp_rotated: DFF::new(Vector3::default()),
         }
     }
 }
 
+// decodorul indexului de context (one-hot)
+#[kernel]
+pub fn ctx_onehot(id: b2) -> [bool; POSE_CONTEXTS] {
+    [id == bits(0), id == bits(1), id == bits(2), id == bits(3)]
+}
+
+// pose-ul contextului 'id' (multiplexor 4:1)
+#[kernel]
+pub fn select_context(poses: PoseContexts, id: b2) -> Matrix4x4 {
+    let mut pose = poses[0];
+    if id == bits(1) {
+        pose = poses[1];
+    } else if id == bits(2) {
+        pose = poses[2];
+    } else if id == bits(3) {
+        pose = poses[3];
+    }
+    pose
+}
+
 // interfata io
 impl SynchronousIO for Engine {
     type I = (LidarInput, ControlSignals);
@@ -48,23 +89,66 @@ pub fn engine_kernel(
     // aici avem doua operatii distincte
 
     // calculam rotatia curenta: R * P
-    let current_rotation_res = matrix_vector_mult(q.rotation, q.point);
+    let current_rotation_res = matrix_vector_mult(q.pose.rotation, q.point);
 
     // calculam translatia finala: P_rot + T
-    let final_res = vector_add(q.temp_rotated, q.translation);
+    let final_res = vector_add(q.temp_rotated, q.pose.translation);
 
     // 2. definim starea viitoare (d)
     // initializam d cu valorile curente (hold state)
     let mut next_point = q.point;
-    let mut next_rotation = q.rotation;
-    let mut next_translation = q.translation;
+    let mut next_pose = q.pose;
+    let mut next_contexts = q.contexts;
     let mut next_temp = q.temp_rotated;
+    let mut next_shadow_pose = q.shadow_pose;
+    let mut next_swap_pending = q.swap_pending;
+    let mut next_ctx_id = q.ctx_id;
+    let mut next_first = q.first;
+    let mut next_last = q.last;
+    let mut next_debug_sel = q.debug_sel;
+
+    // scrierea pose-ului urmator in shadow-ul contextului pose_ctx, independent de starea automatului
+    // cu pose_compose transformarea primita se inmulteste la dreapta celei deja scrise
+    // exista o singura unitate de compunere, partajata intre contexte
+    let write_sel = ctx_onehot(data_in.pose_ctx);
+    let written = Matrix4x4 {
+        rotation: data_in.rotation,
+        translation: data_in.translation,
+    };
+    let mut new_shadow = written;
+    if data_in.pose_compose {
+        new_shadow = affine_compose(select_context(q.shadow_pose, data_in.pose_ctx), written);
+    }
+    for i in 0..4 {
+        if data_in.pose_write && write_sel[i] {
+            next_shadow_pose[i] = new_shadow;
+        }
+        next_swap_pending[i] = q.swap_pending[i] || (data_in.pose_swap && write_sel[i]);
+    }
 
     // logica de incarcare
+    // swap-urile se aplica doar intre puncte, ca punctul aflat in calcul sa nu vada un pose amestecat
     if cs.load_input {
         next_point = data_in.point;
-        next_rotation = data_in.rotation;
-        next_translation = data_in.translation;
+        next_first = data_in.first;
+        next_last = data_in.last;
+        next_debug_sel = data_in.debug_sel;
+        next_ctx_id = data_in.ctx_id;
+        if data_in.velocity_mode {
+            // pose-ul punctului vine din viteze; shadow-urile si swap-urile cerute raman pentru punctele normale
+            next_pose = velocity_pose(data_in.omega, data_in.velocity, data_in.dt);
+        } else {
+            // toate contextele cu swap cerut trec pe shadow, apoi punctul isi ia pose-ul din contextul lui
+            let mut contexts = q.contexts;
+            for i in 0..4 {
+                if q.swap_pending[i] {
+                    contexts[i] = q.shadow_pose[i];
+                    next_swap_pending[i] = data_in.pose_swap && write_sel[i];
+                }
+            }
+            next_contexts = contexts;
+            next_pose = select_context(contexts, data_in.ctx_id);
+        }
     }
 
     // logica de salvare intermediara
@@ -72,17 +156,46 @@ pub fn engine_kernel(
         next_temp = current_rotation_res;
     }
 
+    // selectia de depanare: punctul brut sau rezultatul intermediar in locul celui final
+    let mut selected = final_res;
+    match q.debug_sel {
+        DebugSel::Final => {},
+        DebugSel::Raw => {
+            selected = q.point;
+        },
+        DebugSel::Rotated => {
+            selected = q.temp_rotated;
+        },
+    }
+
     // 3. construim iesirea
+    // pixelul, formatul compact, campurile de scanare, status si bist sunt completate in top
     let output = LidarOutput {
         valid: cs.output_valid,
-        corrected_point: final_res,
+        corrected_point: selected,
+        pixel: RangePixel::default(),
+        compact: CompactPoint::default(),
+        ctx_id: q.ctx_id,
+        first: q.first,
+        last: q.last,
+        scan_done: false,
+        scan_count: bits(0),
+        ready: false,
+        status: PerfStatus::default(),
+        bist: BistStatus::default(),
     };
 
     // returnam iesirea si noua stare
     (output, D {
         point: next_point,
-        rotation: next_rotation,
-        translation: next_translation,
+        pose: next_pose,
+        contexts: next_contexts,
+        shadow_pose: next_shadow_pose,
+        swap_pending: next_swap_pending,
+        ctx_id: next_ctx_id,
+        first: next_first,
+        last: next_last,
+        debug_sel: next_debug_sel,
         temp_rotated: next_temp,
     })
 }
\ No newline at end of file
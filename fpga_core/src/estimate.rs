@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use rhdl::prelude::*;
+use rhdl::core::rhif::Object;
+use rhdl::core::rhif::spec::{AluBinary, OpCode, Slot};
+
+// estimare grosiera de resurse si timing, fara tool-urile vendorului
+//
+// fiecare kernel e compilat in RHIF (compile_design) si operatiile sunt numarate:
+//   - inmultiri: latimea operanzilor -> numarul de DSP48 (port 25 x 18 cu semn)
+//   - adunari/scaderi, comparatii, multiplexoare (select si match), shiftari
+//   - apelurile de sub-kernel sunt estimate recursiv si adunate la apelant
+// o operatie RHIF necunoscuta opreste estimarea cu eroare, ca cifrele sa nu iasa subestimate fara sa stim
+// drumul combinational cel mai lung se estimeaza propagand un timp de sosire prin operatii, in ordinea din RHIF
+// modelul de intarziere e aproximativ (familia 7-series), util doar pentru a compara variante intre ele
+
+// intarzieri aproximative in ns
+const DSP_DELAY_NS: f64 = 3.0; // un DSP48 fara registre interne
+const CARRY_BASE_NS: f64 = 0.5; // intrare in lantul de carry
+const CARRY_PER_BIT_NS: f64 = 0.015; // propagare pe un bit din CARRY4
+const LUT_DELAY_NS: f64 = 0.4; // un nivel de LUT + rutare
+
+#[derive(Debug, Default, Clone)]
+pub struct KernelEstimate {
+    pub name: String,
+    pub multipliers: usize,
+    pub dsp: usize,
+    pub adders: usize,
+    pub adder_bits: usize,
+    pub comparators: usize,
+    pub muxes: usize,
+    pub mux_bits: usize,
+    pub shifts: usize,
+    pub critical_path_ns: f64,
+}
+
+// de ce nu s-a putut estima un kernel
+#[derive(Debug)]
+pub enum EstimateError {
+    // compilarea in RHIF a esuat
+    Compile(RHDLError),
+    // operatie RHIF pe care modelul nu o acopera
+    UnknownOp { kernel: String, op: String },
+    // apel catre un sub-kernel care lipseste din tabela externals
+    MissingExternal { kernel: String, id: String },
+}
+
+impl std::fmt::Display for EstimateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EstimateError::Compile(e) => write!(f, "compilarea RHIF a esuat: {}", e),
+            EstimateError::UnknownOp { kernel, op } => {
+                write!(f, "operatia RHIF {} din {} nu este estimata", op, kernel)
+            }
+            EstimateError::MissingExternal { kernel, id } => {
+                write!(f, "sub-kernelul {} apelat din {} lipseste din externals", id, kernel)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EstimateError {}
+
+impl From<RHDLError> for EstimateError {
+    fn from(e: RHDLError) -> Self {
+        EstimateError::Compile(e)
+    }
+}
+
+// numarul de DSP48 pentru o inmultire a x b (cu semn)
+pub fn dsp_count(a_bits: usize, b_bits: usize) -> usize {
+    let (wide, narrow) = if a_bits >= b_bits { (a_bits, b_bits) } else { (b_bits, a_bits) };
+    wide.div_ceil(25) * narrow.div_ceil(18)
+}
+
+fn slot_bits(obj: &Object, slot: &Slot) -> usize {
+    obj.kind(*slot).bits()
+}
+
+impl KernelEstimate {
+    // resursele unui sub-kernel apelat (fiecare apel e o instanta separata in hardware)
+    fn add_resources(&mut self, sub: &KernelEstimate) {
+        self.multipliers += sub.multipliers;
+        self.dsp += sub.dsp;
+        self.adders += sub.adders;
+        self.adder_bits += sub.adder_bits;
+        self.comparators += sub.comparators;
+        self.muxes += sub.muxes;
+        self.mux_bits += sub.mux_bits;
+        self.shifts += sub.shifts;
+    }
+}
+
+// estimarea unui kernel compilat
+pub fn estimate_object(name: &str, obj: &Object) -> Result<KernelEstimate, EstimateError> {
+    let mut est = KernelEstimate { name: name.to_string(), ..Default::default() };
+    let mut arrival: BTreeMap<Slot, f64> = BTreeMap::new();
+    let at = |arrival: &BTreeMap<Slot, f64>, slot: &Slot| arrival.get(slot).copied().unwrap_or(0.0);
+
+    for lop in &obj.ops {
+        let (lhs, inputs, delay) = match &lop.op {
+            OpCode::Binary(op) => {
+                let bits = slot_bits(obj, &op.lhs);
+                let delay = match op.op {
+                    AluBinary::Mul => {
+                        let dsp = dsp_count(slot_bits(obj, &op.arg1), slot_bits(obj, &op.arg2));
+                        est.multipliers += 1;
+                        est.dsp += dsp;
+                        // DSP-urile in cascada adauga cate o intarziere
+                        DSP_DELAY_NS * dsp.min(4) as f64
+                    }
+                    AluBinary::Add | AluBinary::Sub => {
+                        est.adders += 1;
+                        est.adder_bits += bits;
+                        CARRY_BASE_NS + CARRY_PER_BIT_NS * bits as f64
+                    }
+                    AluBinary::Eq | AluBinary::Ne | AluBinary::Lt | AluBinary::Le | AluBinary::Gt | AluBinary::Ge => {
+                        est.comparators += 1;
+                        let width = slot_bits(obj, &op.arg1);
+                        CARRY_BASE_NS + CARRY_PER_BIT_NS * width as f64
+                    }
+                    AluBinary::Shl | AluBinary::Shr => {
+                        // shiftarile cu constanta sunt doar rutare; cele variabile sunt un barrel shifter
+                        if matches!(op.arg2, Slot::Literal(_)) {
+                            0.0
+                        } else {
+                            est.shifts += 1;
+                            LUT_DELAY_NS * (bits as f64).log2().ceil()
+                        }
+                    }
+                    _ => LUT_DELAY_NS,
+                };
+                (op.lhs, vec![op.arg1, op.arg2], delay)
+            }
+            OpCode::Unary(op) => (op.lhs, vec![op.arg1], LUT_DELAY_NS),
+            OpCode::Select(op) => {
+                est.muxes += 1;
+                est.mux_bits += slot_bits(obj, &op.lhs);
+                (op.lhs, vec![op.cond, op.true_value, op.false_value], LUT_DELAY_NS)
+            }
+            // match: un multiplexor N:1, adica N - 1 multiplexoare 2:1; un LUT6 alege din 4 intrari pe nivel
+            OpCode::Case(op) => {
+                let ways = op.table.len().max(2);
+                est.muxes += ways - 1;
+                est.mux_bits += slot_bits(obj, &op.lhs) * (ways - 1);
+                let levels = ((ways as f64).log2() / 2.0).ceil().max(1.0);
+                let mut inputs = vec![op.discriminant];
+                inputs.extend(op.table.iter().map(|(_, value)| *value));
+                (op.lhs, inputs, LUT_DELAY_NS * levels)
+            }
+            // apel de sub-kernel: resursele lui se adauga, iar rezultatul vine dupa drumul lui critic
+            // (aproximare pesimista: drumul cel mai lung din sub-kernel pleaca de la ultimul argument sosit)
+            OpCode::Exec(op) => {
+                let external = obj.externals.get(&op.id).ok_or_else(|| EstimateError::MissingExternal {
+                    kernel: name.to_string(),
+                    id: format!("{:?}", op.id),
+                })?;
+                let sub = estimate_object(name, external)?;
+                est.add_resources(&sub);
+                (op.lhs, op.args.clone(), sub.critical_path_ns)
+            }
+            // restul operatiilor (index, splice, struct, cast...) sunt doar rutare
+            OpCode::Index(op) => (op.lhs, vec![op.arg], 0.0),
+            OpCode::Splice(op) => (op.lhs, vec![op.orig, op.subst], 0.0),
+            OpCode::Assign(op) => (op.lhs, vec![op.rhs], 0.0),
+            OpCode::Resize(op) | OpCode::AsBits(op) | OpCode::AsSigned(op) => (op.lhs, vec![op.arg], 0.0),
+            OpCode::Struct(op) => (op.lhs, op.fields.iter().map(|f| f.value).collect(), 0.0),
+            OpCode::Tuple(op) => (op.lhs, op.fields.clone(), 0.0),
+            OpCode::Array(op) => (op.lhs, op.elements.clone(), 0.0),
+            OpCode::Enum(op) => (op.lhs, op.fields.iter().map(|f| f.value).collect(), 0.0),
+            OpCode::Repeat(op) => (op.lhs, vec![op.value], 0.0),
+            OpCode::Noop | OpCode::Comment(_) => continue,
+            other => {
+                return Err(EstimateError::UnknownOp { kernel: name.to_string(), op: format!("{:?}", other) });
+            }
+        };
+
+        let start = inputs.iter().map(|s| at(&arrival, s)).fold(0.0, f64::max);
+        let done = start + delay;
+        arrival.insert(lhs, done);
+        est.critical_path_ns = est.critical_path_ns.max(done);
+    }
+
+    Ok(est)
+}
+
+// compileaza kernelul K si il estimeaza
+pub fn estimate_kernel<K: DigitalFn>(name: &str) -> Result<KernelEstimate, EstimateError> {
+    let obj = compile_design::<K>(CompilationMode::Synchronous)?;
+    estimate_object(name, &obj)
+}
+
+// bitii de registru ai unei componente care are doar DFF-uri (si Constant) drept copii:
+// Q contine exact iesirile DFF-urilor, iar Constant-urile se scad separat
+pub fn register_bits<Q: Digital>() -> usize {
+    Q::BITS
+}
+
+pub fn print_table(title: &str, rows: &[KernelEstimate], register_bits: usize) {
+    println!("{}", title);
+    println!("{:<26} {:>5} {:>5} {:>7} {:>9} {:>6} {:>9} {:>7} {:>9}",
+             "kernel", "mul", "DSP", "add", "add bits", "cmp", "mux bits", "shift", "path ns");
+    for r in rows {
+        println!("{:<26} {:>5} {:>5} {:>7} {:>9} {:>6} {:>9} {:>7} {:>9.2}",
+                 r.name, r.multipliers, r.dsp, r.adders, r.adder_bits, r.comparators, r.mux_bits, r.shifts,
+                 r.critical_path_ns);
+    }
+
+    let dsp: usize = rows.iter().map(|r| r.dsp).sum();
+    let worst = rows.iter().map(|r| r.critical_path_ns).fold(0.0, f64::max);
+    println!("total: {} DSP, {} biti de registru, drum critic ~{:.2} ns (~{:.0} MHz)",
+             dsp, register_bits, worst, if worst > 0.0 { 1000.0 / worst } else { 0.0 });
+    println!();
+}
@@ -0,0 +1,115 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::constant::Constant;
+use crate::types::*;
+
+// etaj optional de formatare la iesire: punctul corectat (16.16, metri) -> intregi in mm sau cm
+//
+// rotunjirea e la cel mai apropiat intreg (jumatatile in sus): (v * scale + 2^15) >> 16
+// pe 16 biti valorile sunt saturate (mm: +-32.7 m, cm: +-327 m), pe 32 biti nu pot depasi
+// etajul e combinational (o inmultire cu o constanta si un sumator pe coordonata), nu adauga latenta
+// corrected_point ramane neschimbat; formatul compact e in LidarOutput.compact si pe magistrala (bus.rs)
+
+// configurarea, fixata la sinteza
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct OutputFormat {
+    pub enable: bool,
+    pub unit: CompactUnit,
+    // 32 biti pe coordonata in loc de 16
+    pub wide: bool,
+}
+
+impl OutputFormat {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn new(unit: CompactUnit, wide: bool) -> Self {
+        Self { enable: true, unit, wide }
+    }
+}
+
+// o coordonata 16.16 -> unitati intregi, rotunjit si (pe 16 biti) saturat
+#[kernel]
+pub fn to_units(v: Fixed32, fmt: OutputFormat) -> s32 {
+    let wide: s48 = v.resize();
+    let mut scaled = wide * signed(1000);
+    match fmt.unit {
+        CompactUnit::Millimetre => {},
+        CompactUnit::Centimetre => {
+            scaled = wide * signed(100);
+        },
+    }
+    let rounded = (scaled + signed(32768)) >> 16;
+
+    let mut units: s32 = rounded.resize();
+    if !fmt.wide {
+        if rounded > signed(32767) {
+            units = signed(32767);
+        } else if rounded < signed(-32768) {
+            units = signed(-32768);
+        }
+    }
+    units
+}
+
+// coordonatele convertite, impachetate ca in CompactPoint
+#[kernel]
+pub fn pack_compact(fmt: OutputFormat, p: Vector3) -> CompactPoint {
+    let x: b96 = to_units(p.x, fmt).as_unsigned().resize();
+    let y: b96 = to_units(p.y, fmt).as_unsigned().resize();
+    let z: b96 = to_units(p.z, fmt).as_unsigned().resize();
+
+    let mut data = x | (y << 32) | (z << 64);
+    if !fmt.wide {
+        let mask: b96 = bits(0xFFFF);
+        data = (x & mask) | ((y & mask) << 16) | ((z & mask) << 32);
+    }
+
+    CompactPoint {
+        valid: true,
+        wide: fmt.wide,
+        unit: fmt.unit,
+        data,
+    }
+}
+
+// etajul de formatare, pus dupa proiectie
+// inactiv: compact ramane invalid, sinteza elimina conversia
+#[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+pub struct OutputFormatter {
+    cfg: Constant<OutputFormat>,
+}
+
+impl OutputFormatter {
+    pub fn new(cfg: OutputFormat) -> Self {
+        Self {
+            cfg: Constant::new(cfg),
+        }
+    }
+}
+
+impl Default for OutputFormatter {
+    fn default() -> Self {
+        Self::new(OutputFormat::disabled())
+    }
+}
+
+// interfata io
+impl SynchronousIO for OutputFormatter {
+    type I = LidarOutput;
+    type O = LidarOutput;
+    type Kernel = output_format_kernel;
+}
+
+#[kernel]
+pub fn output_format_kernel(_cr: ClockReset, input: LidarOutput, q: Q) -> (LidarOutput, D) {
+    let cfg = q.cfg;
+
+    let mut output = input;
+    if cfg.enable {
+        output.compact = pack_compact(cfg, input.corrected_point);
+        output.compact.valid = input.valid;
+    }
+
+    (output, D { cfg: () })
+}
@@ -0,0 +1,310 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::constant::Constant;
+use rhdl_fpga::core::dff::DFF;
+use crate::types::*;
+use crate::alu::fixed_abs;
+
+// unitate secventiala pentru impartire, radacina patrata, reciproc si inversul radacinii,
+// varianta ieftina a lui fixed_div / fixed_sqrt / fixed_recip / fixed_rsqrt din alu
+// aceeasi recurenta (restoring / cifra cu cifra), dar cu 1 sau 2 iteratii pe ciclu in loc de toate desfasurate
+//
+// optiunile (fixate la sinteza):
+//   - double_step: 2 iteratii pe ciclu, latenta la jumatate, drumul critic dublu
+//   - frac_bits: cati biti de fractie calculam (0..16); mai putini = mai putine iteratii, rezultat trunchiat mai grosier
+//
+// notam A = a * 2^16 valoarea bruta 16.16; numaratorul se incarca aliniat la stanga pe 64 biti (A << 32),
+// fiecare iteratie consuma bitii lui de sus, iar rezultatul final e shiftat cu 16 - frac_bits
+//
+// impartire: cat = (A << frac_bits) / B, 32 + frac_bits iteratii (consuma primii 32 + frac_bits biti)
+// radacina: isqrt(A << (2 * frac_bits - 16)) = sqrt(a) * 2^frac_bits, 8 + frac_bits iteratii (consuma primii 16 + 2 * frac_bits biti)
+// reciproc: impartirea lui 1.0 (2^16); bitii de sus ai lui 1.0 << 32 sunt zero, asa ca numaratorul incepe
+//   direct cu 1 pe bitul 63 si raman 17 + frac_bits iteratii
+// inversul radacinii: radacina, apoi reciprocul ei, una dupa alta pe aceeasi recurenta (ca fixed_rsqrt)
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub enum IterOp {
+    #[default]
+    Div,
+    Sqrt,
+    Recip,
+    Rsqrt,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct IterConfig {
+    pub double_step: bool,
+    pub frac_bits: b5,
+}
+
+impl IterConfig {
+    // precizie completa (identic cu fixed_div / fixed_sqrt), o iteratie pe ciclu
+    pub fn full() -> Self {
+        Self { double_step: false, frac_bits: bits(16) }
+    }
+
+    // precizie completa, doua iteratii pe ciclu
+    pub fn fast() -> Self {
+        Self { double_step: true, frac_bits: bits(16) }
+    }
+
+    // mai putini biti de fractie (0..16), pentru cand precizia de 2^-16 nu e necesara
+    pub fn with_frac_bits(self, frac: u8) -> Self {
+        assert!(frac <= 16, "frac_bits trebuie sa fie intre 0 si 16");
+        Self { frac_bits: bits(frac as u128), ..self }
+    }
+
+    pub fn iterations(&self, op: IterOp) -> usize {
+        let frac = self.frac_bits.raw() as usize;
+        match op {
+            IterOp::Div => 32 + frac,
+            IterOp::Sqrt => 8 + frac,
+            IterOp::Recip => 17 + frac,
+            IterOp::Rsqrt => self.iterations(IterOp::Sqrt) + self.iterations(IterOp::Recip),
+        }
+    }
+
+    // cicluri de la start pana la valid (un ciclu de incarcare + iteratiile + registrul de iesire);
+    // la inversul radacinii fiecare faza se termina pe ciclul ei
+    pub fn latency(&self, op: IterOp) -> usize {
+        let steps = if self.double_step { 2 } else { 1 };
+        let cycles = match op {
+            IterOp::Rsqrt => self.iterations(IterOp::Sqrt).div_ceil(steps) + self.iterations(IterOp::Recip).div_ceil(steps),
+            _ => self.iterations(op).div_ceil(steps),
+        };
+        cycles + 1
+    }
+}
+
+// interfata io
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct IterInput {
+    pub start: bool,
+    pub op: IterOp,
+    pub a: Fixed32,
+    pub b: Fixed32,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct IterOutput {
+    pub valid: bool,
+    pub busy: bool,
+    pub result: Fixed32,
+}
+
+// starea recurentei
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct IterState {
+    pub num: b64,   // numaratorul ramas, aliniat la stanga
+    pub rem: b50,   // restul partial
+    pub quot: b48,  // catul / radacina partiala
+    pub count: b6,  // iteratii ramase
+}
+
+// un pas al impartirii restoring: urmatorul bit al numaratorului intra in rest
+#[kernel]
+pub fn div_step(divisor: b48, s: IterState) -> IterState {
+    let mut next = s;
+    let top: b50 = (s.num >> 63).resize();
+    let rem = (s.rem << 1) | top;
+    let d: b50 = divisor.resize();
+    next.num = s.num << 1;
+    next.quot = s.quot << 1;
+    next.rem = rem;
+    if rem >= d {
+        next.rem = rem - d;
+        next.quot = next.quot | bits(1);
+    }
+    next
+}
+
+// un pas al radacinii cifra cu cifra: urmatorii 2 biti ai numaratorului intra in rest
+#[kernel]
+pub fn sqrt_step(s: IterState) -> IterState {
+    let mut next = s;
+    let top: b50 = (s.num >> 62).resize();
+    let rem = (s.rem << 2) | top;
+    let q: b50 = s.quot.resize();
+    let trial = (q << 2) | bits(1);
+    next.num = s.num << 2;
+    next.quot = s.quot << 1;
+    next.rem = rem;
+    if rem >= trial {
+        next.rem = rem - trial;
+        next.quot = next.quot | bits(1);
+    }
+    next
+}
+
+// starea de inceput a reciprocului: 1.0 aliniat la bitul 63 (vezi comentariul de sus)
+#[kernel]
+pub fn recip_start(frac_bits: b5) -> IterState {
+    let frac: b6 = frac_bits.resize();
+    IterState {
+        num: bits(0x8000_0000_0000_0000),
+        rem: bits(0),
+        quot: bits(0),
+        count: bits(17) + frac,
+    }
+}
+
+// o iteratie; fara efect cand nu mai sunt iteratii
+// (Rsqrt ruleaza aici doar faza de radacina, faza a doua e deja Recip)
+#[kernel]
+pub fn iter_step(op: IterOp, divisor: b48, s: IterState) -> IterState {
+    let mut next = s;
+    if s.count != bits(0) {
+        next = div_step(divisor, s);
+        match op {
+            IterOp::Div => {}
+            IterOp::Recip => {}
+            IterOp::Sqrt => {
+                next = sqrt_step(s);
+            }
+            IterOp::Rsqrt => {
+                next = sqrt_step(s);
+            }
+        }
+        next.count = s.count - bits(1);
+    }
+    next
+}
+
+#[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+pub struct IterativeUnit {
+    cfg: Constant<IterConfig>,
+    busy: DFF<bool>,
+    op: DFF<IterOp>,
+    neg: DFF<bool>,
+    divisor: DFF<b48>,
+    state: DFF<IterState>,
+    out: DFF<IterOutput>,
+}
+
+impl IterativeUnit {
+    pub fn new(cfg: IterConfig) -> Self {
+        Self {
+            cfg: Constant::new(cfg),
+            busy: DFF::new(false),
+            op: DFF::new(IterOp::Div),
+            neg: DFF::new(false),
+            divisor: DFF::new(bits(0)),
+            state: DFF::new(IterState::default()),
+            out: DFF::new(IterOutput::default()),
+        }
+    }
+}
+
+impl Default for IterativeUnit {
+    fn default() -> Self {
+        Self::new(IterConfig::full())
+    }
+}
+
+impl SynchronousIO for IterativeUnit {
+    type I = IterInput;
+    type O = IterOutput;
+    type Kernel = iterative_kernel;
+}
+
+#[kernel]
+pub fn iterative_kernel(_cr: ClockReset, input: IterInput, q: Q) -> (IterOutput, D) {
+    let cfg = q.cfg;
+
+    let mut d = D::dont_care();
+    d.cfg = ();
+    d.busy = q.busy;
+    d.op = q.op;
+    d.neg = q.neg;
+    d.divisor = q.divisor;
+    d.state = q.state;
+
+    let mut done = IterOutput {
+        valid: false,
+        busy: q.busy,
+        result: q.out.result,
+    };
+
+    if q.busy {
+        // iteratiile din ciclul curent
+        let mut s = iter_step(q.op, q.divisor, q.state);
+        if cfg.double_step {
+            s = iter_step(q.op, q.divisor, s);
+        }
+        d.state = s;
+
+        if s.count == bits(0) {
+            // alinierea la 16.16 si saturarea (impartirea la 0 da catul plin de 1)
+            let shift: b5 = bits(16) - cfg.frac_bits;
+            let wide = s.quot << shift;
+            let mut mag: s32 = signed(0x7FFFFFFF);
+            if wide <= bits(0x7FFFFFFF) {
+                let w32: b32 = wide.resize();
+                mag = w32.as_signed();
+            }
+            let mut result = mag;
+            if q.neg {
+                result = -mag;
+            }
+            let mut finished = true;
+            match q.op {
+                IterOp::Div => {}
+                IterOp::Recip => {}
+                IterOp::Sqrt => {}
+                IterOp::Rsqrt => {
+                    // s-a terminat radacina: continuam cu reciprocul ei (radacina 16.16 nu satureaza)
+                    finished = false;
+                    d.op = IterOp::Recip;
+                    d.divisor = wide;
+                    d.state = recip_start(cfg.frac_bits);
+                }
+            }
+            if finished {
+                done = IterOutput { valid: true, busy: false, result };
+                d.busy = false;
+            }
+        }
+    } else if input.start {
+        // incarcare: operandul aliniat la stanga (vezi comentariul de sus)
+        let frac: b6 = cfg.frac_bits.resize();
+        let a_wide: b64 = fixed_abs(input.a).resize();
+        // radacina dintr-un numar negativ e 0
+        let mut root_num = a_wide << 32;
+        if input.a < signed(0) {
+            root_num = bits(0);
+        }
+        let mut neg = (input.a < signed(0)) != (input.b < signed(0));
+        let mut state = IterState {
+            num: a_wide << 32,
+            rem: bits(0),
+            quot: bits(0),
+            count: bits(32) + frac,
+        };
+        match input.op {
+            IterOp::Div => {}
+            IterOp::Recip => {
+                neg = input.b < signed(0);
+                state = recip_start(cfg.frac_bits);
+            }
+            IterOp::Sqrt => {
+                neg = false;
+                state.num = root_num;
+                state.count = bits(8) + frac;
+            }
+            IterOp::Rsqrt => {
+                neg = false;
+                state.num = root_num;
+                state.count = bits(8) + frac;
+            }
+        }
+
+        d.busy = true;
+        d.op = input.op;
+        d.neg = neg;
+        d.divisor = fixed_abs(input.b).resize();
+        d.state = state;
+        done.busy = true;
+    }
+
+    d.out = done;
+    (q.out, d)
+}
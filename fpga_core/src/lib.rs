@@ -3,26 +3,66 @@ use rhdl::prelude::*;
 pub mod types;
 pub mod alu;
 pub mod control_unit;
+pub mod coverage;
 pub mod engine;
+pub mod estimate;
+pub mod format;
+pub mod iterative;
+pub mod bist;
+pub mod bus;
+pub mod uart;
+pub mod multiplier;
+pub mod perf;
+pub mod projection;
+pub mod scan;
+pub mod sim;
 
 // Importăm doar numele structurilor, NU și Q/D-ul lor intern.
-pub use types::{LidarInput, LidarOutput, Vector3, Matrix3x3};
+pub use types::{LidarInput, LidarOutput, PerfStatus, BistStatus, Vector3, Matrix3x3, Matrix4x4, RangePixel, CompactPoint, CompactUnit};
 pub use control_unit::{ControlUnit, ControlSignals};
 pub use engine::Engine;
+pub use perf::{PerfCounters, PerfEvents};
+pub use bist::{Bist, BistInput};
+pub use scan::{ScanTracker, ScanEvents};
+pub use projection::{RangeProjector, RangeImageConfig};
+pub use format::{OutputFormatter, OutputFormat};
 
 #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
 pub struct LidarProcessor {
     cu: ControlUnit,
     engine: Engine,
+    projector: RangeProjector,
+    formatter: OutputFormatter,
+    perf: PerfCounters,
+    bist: Bist,
+    scan: ScanTracker,
 }
 
-impl Default for LidarProcessor {
-    fn default() -> Self {
+impl LidarProcessor {
+    // procesorul cu etajul de proiectie in range image configurat
+    pub fn new(range_image: RangeImageConfig) -> Self {
         Self {
             cu: ControlUnit::default(),
             engine: Engine::default(),
+            projector: RangeProjector::new(range_image),
+            formatter: OutputFormatter::default(),
+            perf: PerfCounters::default(),
+            bist: Bist::default(),
+            scan: ScanTracker::default(),
         }
     }
+
+    // cu etajul de formatare activ (coordonate intregi in LidarOutput.compact si pe magistrala)
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.formatter = OutputFormatter::new(format);
+        self
+    }
+}
+
+impl Default for LidarProcessor {
+    fn default() -> Self {
+        Self::new(RangeImageConfig::disabled())
+    }
 }
 
 impl SynchronousIO for LidarProcessor {
@@ -39,17 +79,58 @@ pub fn top_kernel(_cr: ClockReset, input: LidarInput, q: Q) -> (LidarOutput, D)
     // initializam structura de intrari (D) pentru componente
     let mut d = D::dont_care();
 
+    // in modul BIST intrarea vine din ROM-ul de test, nu de la host
+    let mut core_in = input;
+    if q.bist.active {
+        core_in = q.bist.drive;
+    }
+
     // conectam Control Unit
     // Input-ul lui este doar semnalul valid
-    d.cu = input.valid;
+    d.cu = core_in.valid;
 
     // conectam engine
     // input-ul lui este (Date Lidar, Comenzi)
     // q.cu reprezintă iesirea curentă a unității de control (de tip ControlSignals)
-    d.engine = (input, q.cu);
+    d.engine = (core_in, q.cu);
+
+    // BIST-ul vede rezultatele Engine-ului
+    d.bist = BistInput {
+        enable: input.bist_enable,
+        result_valid: q.engine.valid,
+        result: q.engine.corrected_point,
+    };
+
+    // etajul de proiectie (optional) primeste punctul corectat
+    // rezultatele vectorilor de test nu ajung la host, le oprim inainte de proiectie
+    let mut corrected = q.engine;
+    corrected.valid = q.engine.valid && !q.bist.active;
+    d.projector = corrected;
+
+    // contoarele de performanta observa intrarea, automatul si iesirea finala
+    d.perf = PerfEvents {
+        valid_in: core_in.valid,
+        busy: q.cu.busy,
+        emitted: q.projector.valid,
+    };
+
+    // formatarea (optionala) in unitati intregi, fara latenta
+    d.formatter = q.projector;
 
     // iesirea Sistemului
-    let output = q.engine;
+    let mut output = q.formatter;
+    output.ready = !q.cu.busy && !q.bist.active;
+    output.status = q.perf;
+    output.bist = q.bist.status;
+
+    // numaram doar punctele care ajung la host
+    d.scan = ScanEvents {
+        valid: output.valid,
+        first: output.first,
+        last: output.last,
+    };
+    output.scan_done = q.scan.scan_done;
+    output.scan_count = q.scan.scan_count;
 
     (output, d)
 }
\ No newline at end of file
@@ -1,5 +1,17 @@
 use rhdl::prelude::*;
-use fpga_core::{LidarProcessor, LidarInput, Vector3, Matrix3x3};
+use fpga_core::{LidarProcessor, LidarInput, LidarOutput, Vector3, Matrix3x3, Matrix4x4};
+use fpga_core::types::{DebugSel, RotFixed, POSE_CONTEXTS};
+use fpga_core::alu::{fixed_mul, affine_apply, affine_compose, affine_inverse};
+use fpga_core::alu::{fixed_div, fixed_recip, fixed_sqrt, fixed_rsqrt, vector_norm, vector_normalize};
+use fpga_core::iterative::{IterativeUnit, IterConfig, IterInput, IterOp};
+use fpga_core::multiplier::{DspMul, MulInput, MulPipeline};
+use fpga_core::sim::cycle_samples;
+use fpga_core::bist::{Bist, BistInput, bist_rom, BIST_VECTORS};
+use fpga_core::coverage::{traced_run, Coverage};
+use fpga_core::projection::RangeImageConfig;
+use fpga_core::{CompactPoint, CompactUnit, OutputFormat};
+use fpga_core::bus::{BusLidarProcessor, BusWord32, input_word, INPUT_WORDS, OUTPUT_WORDS};
+use fpga_core::bus::{BusWord64, WordDeserializer64, WordSerializer64, output_word};
 
 // magie pentru a afisa erorile din acest limbaj criptic
 fn miette_report(err: RHDLError) -> String {
@@ -9,12 +21,28 @@ fn miette_report(err: RHDLError) -> String {
     msg
 }
 
+// simularea unui test (un esantion pe ciclu), cu acoperirea stransa din aceeasi rulare
+// o eroare la acoperire nu pica testul, doar lipseste rularea din raport
+fn simulate<T: Synchronous>(cov: &mut Coverage, uut: &T, inputs: Vec<T::I>) -> Result<Vec<(T::I, T::O)>, RHDLError> {
+    let stream = inputs.into_iter().with_reset(1).clock_pos_edge(100);
+    let (samples, vcd) = traced_run(uut.run(stream)?);
+    if let Err(e) = cov.add_run(vcd) {
+        println!("Acoperirea nu a putut fi colectata: {}", e);
+    }
+    Ok(cycle_samples(samples))
+}
+
 // conversie Int -> Fixed Point (format 16.16)
 fn tofixedpointvar(v: i32) -> s32 {
     s32::from(v as i128) << 16
 }
 
-fn run_simulation() -> Result<(), RHDLError> {
+// conversie Int -> coeficient de rotatie (format Q2.16)
+fn torotvar(v: i32) -> RotFixed {
+    RotFixed::from(v as i128) << 16
+}
+
+fn run_simulation(cov: &mut Coverage) -> Result<(), RHDLError> {
     // instantiem procesorul
     let uut = LidarProcessor::default();
 
@@ -32,10 +60,10 @@ fn run_simulation() -> Result<(), RHDLError> {
     let point_in = Vector3 { x: tofixedpointvar(10), y: tofixedpointvar(0), z: tofixedpointvar(0) };
     let trans = Vector3::default(); // Translație zero
 
-    let mut rows = [[tofixedpointvar(0); 3]; 3];
-    rows[0][1] = tofixedpointvar(-1); // -sin(90)
-    rows[1][0] = tofixedpointvar(1);  // sin(90)
-    rows[2][2] = tofixedpointvar(1);  // 1
+    let mut rows = [[torotvar(0); 3]; 3];
+    rows[0][1] = torotvar(-1); // -sin(90)
+    rows[1][0] = torotvar(1);  // sin(90)
+    rows[2][2] = torotvar(1);  // 1
     let rot = Matrix3x3 { rows };
 
     // 3. Construim fluxul de intrări
@@ -49,11 +77,15 @@ fn run_simulation() -> Result<(), RHDLError> {
 
     // -- Etapa 2: Impuls de Date (Valid = true) --
     // Trimitem datele reale timp de 1 ciclu de ceas
+    // pose-ul vine impreuna cu punctul: scris in shadow si activat la incarcare
     inputs.push(LidarInput {
         valid: true,
         point: point_in,
         rotation: rot,
         translation: trans,
+        pose_write: true,
+        pose_swap: true,
+        ..LidarInput::default()
     });
 
     // -- Etapa 3: Procesare (Wait) --
@@ -62,7 +94,10 @@ fn run_simulation() -> Result<(), RHDLError> {
         valid: false,
         point: point_in,
         rotation: rot,
-        translation: trans
+        translation: trans,
+        pose_write: false,
+        pose_swap: false,
+        ..LidarInput::default()
     };
 
     // Așteptăm 10 cicluri
@@ -83,6 +118,12 @@ fn run_simulation() -> Result<(), RHDLError> {
     println!("Se generează fișierul '{}'...", filename);
     vcd.dump_to_file(filename)?;
 
+    // acoperirea din acelasi fisier, fara o a doua rulare
+    match std::fs::read_to_string(filename) {
+        Ok(text) => cov.add_vcd(&text),
+        Err(e) => println!("Acoperirea nu a putut fi colectata: {}", e),
+    }
+
     println!("Succes! Verifică rezultatul în GTKWave.");
     println!("Ar trebui să vezi un semnal 'output_valid' activat spre final,");
     println!("iar 'corrected_point' să aibă valoarea aprox X=0, Y=655360 (10.0).");
@@ -90,13 +131,1059 @@ fn run_simulation() -> Result<(), RHDLError> {
     Ok(())
 }
 
+fn identity_rot() -> Matrix3x3 {
+    let mut rows = [[torotvar(0); 3]; 3];
+    for i in 0..3 {
+        rows[i][i] = torotvar(1);
+    }
+    Matrix3x3 { rows }
+}
+
+fn rot_z90() -> Matrix3x3 {
+    let mut rows = [[torotvar(0); 3]; 3];
+    rows[0][1] = torotvar(-1);
+    rows[1][0] = torotvar(1);
+    rows[2][2] = torotvar(1);
+    Matrix3x3 { rows }
+}
+
+// perioada unui punct prin automat: Idle -> Load -> CalcRot -> CalcTrans
+const POINT_PERIOD: usize = 4;
+
+// testul registrelor de pose dublate
+// punctele vin la fiecare POINT_PERIOD cicluri; pose-ul nou e scris in shadow cat timp automatul
+// lucreaza, iar swap-ul intra intre puncte, deci iesirile trebuie sa ramana la aceeasi cadenta
+fn run_double_buffer_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = LidarProcessor::default();
+
+    // pose A: identitate + T(1, 2, 3), pose B: rotatie Z 90 + T(-5, 0, 0)
+    let trans_a = Vector3 { x: tofixedpointvar(1), y: tofixedpointvar(2), z: tofixedpointvar(3) };
+    let trans_b = Vector3 { x: tofixedpointvar(-5), y: tofixedpointvar(0), z: tofixedpointvar(0) };
+    let num_points = 12;
+    let switch_at = 6; // primul punct care foloseste pose-ul B
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    let mut expected = Vec::new();
+
+    for i in 0..num_points {
+        let k = i as i32 + 1;
+        let point = Vector3 { x: tofixedpointvar(k), y: tofixedpointvar(2 * k), z: tofixedpointvar(-k) };
+
+        // valoarea asteptata, calculata direct in intregi
+        if i < switch_at {
+            expected.push(Vector3 { x: tofixedpointvar(k + 1), y: tofixedpointvar(2 * k + 2), z: tofixedpointvar(-k + 3) });
+        } else {
+            expected.push(Vector3 { x: tofixedpointvar(-2 * k - 5), y: tofixedpointvar(k), z: tofixedpointvar(-k) });
+        }
+
+        for c in 0..POINT_PERIOD {
+            let mut input = LidarInput { point, ..LidarInput::default() };
+            input.valid = c == 0;
+
+            // pose-ul initial A, incarcat odata cu primul punct
+            if i == 0 && c == 0 {
+                input.rotation = identity_rot();
+                input.translation = trans_a;
+                input.pose_write = true;
+                input.pose_swap = true;
+            }
+
+            // pose-ul B e scris in timpul calculului punctului anterior, apoi se cere swap-ul
+            if i == switch_at - 1 && c == 2 {
+                input.rotation = rot_z90();
+                input.translation = trans_b;
+                input.pose_write = true;
+            }
+            if i == switch_at - 1 && c == 3 {
+                input.pose_swap = true;
+            }
+
+            inputs.push(input);
+        }
+    }
+    for _ in 0..POINT_PERIOD {
+        inputs.push(LidarInput::default());
+    }
+
+    let cycles = simulate(cov, &uut, inputs)?;
+
+    let out_cycles: Vec<usize> = cycles.iter()
+        .enumerate()
+        .filter(|(_, (_, o))| o.valid)
+        .map(|(idx, _)| idx)
+        .collect();
+    let results: Vec<Vector3> = cycles.iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| o.corrected_point)
+        .collect();
+
+    let mut ok = true;
+    if results != expected {
+        println!("EROARE: rezultatele nu corespund (primite {}, asteptate {})", results.len(), expected.len());
+        ok = false;
+    }
+
+    // un punct la fiecare POINT_PERIOD cicluri, inclusiv peste schimbarea de pose
+    if out_cycles.windows(2).any(|w| w[1] - w[0] != POINT_PERIOD) {
+        println!("EROARE: throughput-ul nu e constant: {:?}", out_cycles);
+        ok = false;
+    }
+
+    // fiecare punct vine cand nucleul e ready, iar in ciclul urmator automatul e ocupat (ready jos)
+    if cycles.windows(2).any(|w| w[0].0.valid && (!w[0].1.ready || w[1].1.ready)) {
+        println!("EROARE: semnalul ready nu urmareste automatul");
+        ok = false;
+    }
+
+    println!("Pose dublat: {} puncte, swap la punctul {}, {}", results.len(), switch_at, if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// compunerea transformarilor in hardware
+// A = (Rz90, T(1, 0, 0)) se scrie in shadow, apoi B = (I, T(0, 2, 0)) se compune peste el
+// pose-ul rezultat A * B aplicat pe P(1, 0, 0) trebuie sa dea (-1, 1, 0)
+fn run_compose_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = LidarProcessor::default();
+
+    let pose_a = Matrix4x4 {
+        rotation: rot_z90(),
+        translation: Vector3 { x: tofixedpointvar(1), y: tofixedpointvar(0), z: tofixedpointvar(0) },
+    };
+    let pose_b = Matrix4x4 {
+        rotation: identity_rot(),
+        translation: Vector3 { x: tofixedpointvar(0), y: tofixedpointvar(2), z: tofixedpointvar(0) },
+    };
+    let point = Vector3 { x: tofixedpointvar(1), y: tofixedpointvar(0), z: tofixedpointvar(0) };
+    let expected = Vector3 { x: tofixedpointvar(-1), y: tofixedpointvar(1), z: tofixedpointvar(0) };
+
+    let mut ok = true;
+
+    // kernel-urile rulate direct: compunerea si aplicarea trebuie sa fie echivalente cu doi pasi
+    let composed = affine_compose(pose_a, pose_b);
+    if affine_apply(composed, point) != affine_apply(pose_a, affine_apply(pose_b, point)) {
+        println!("EROARE: affine_compose nu corespunde aplicarii succesive");
+        ok = false;
+    }
+    // A^-1 * A trebuie sa fie identitatea
+    let round_trip = affine_compose(affine_inverse(pose_a), pose_a);
+    if round_trip != (Matrix4x4 { rotation: identity_rot(), translation: Vector3::default() }) {
+        println!("EROARE: affine_inverse nu inverseaza pose-ul: {:?}", round_trip);
+        ok = false;
+    }
+    // -(-32768) nu incape in 16.16: translatia inversa ramane la marginea pozitiva, nu se intoarce peste semn
+    let edge = Matrix4x4 {
+        rotation: identity_rot(),
+        translation: Vector3 { x: s32::from(-0x8000_0000i128), ..Vector3::default() },
+    };
+    let edge_inverse = affine_inverse(edge).translation;
+    if edge_inverse != (Vector3 { x: s32::from(0x7FFF_FFFFi128), ..Vector3::default() }) {
+        println!("EROARE: affine_inverse la marginea 16.16: {:?}", edge_inverse);
+        ok = false;
+    }
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    inputs.push(LidarInput {
+        rotation: pose_a.rotation,
+        translation: pose_a.translation,
+        pose_write: true,
+        ..LidarInput::default()
+    });
+    inputs.push(LidarInput {
+        rotation: pose_b.rotation,
+        translation: pose_b.translation,
+        pose_write: true,
+        pose_compose: true,
+        ..LidarInput::default()
+    });
+    // punctul vine cu swap-ul, shadow-ul compus devine activ la incarcare
+    inputs.push(LidarInput { valid: true, point, pose_swap: true, ..LidarInput::default() });
+    for _ in 0..2 * POINT_PERIOD {
+        inputs.push(LidarInput { point, ..LidarInput::default() });
+    }
+
+    let results: Vec<Vector3> = simulate(cov, &uut, inputs)?
+        .into_iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| o.corrected_point)
+        .collect();
+
+    if results != vec![expected] {
+        println!("EROARE: punctul transformat cu pose-ul compus: {:?}", results);
+        ok = false;
+    }
+
+    println!("Compunere pose: {}", if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// selectia de depanare: acelasi punct si pose, trei iesiri diferite
+// pose: Rz90 + T(1, 2, 3), P(10, 0, 0) -> raw (10, 0, 0), rotated (0, 10, 0), final (1, 12, 3)
+fn run_debug_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = LidarProcessor::default();
+    let point = Vector3 { x: tofixedpointvar(10), y: tofixedpointvar(0), z: tofixedpointvar(0) };
+    let trans = Vector3 { x: tofixedpointvar(1), y: tofixedpointvar(2), z: tofixedpointvar(3) };
+
+    let cases = [
+        (DebugSel::Raw, point),
+        (DebugSel::Rotated, Vector3 { x: tofixedpointvar(0), y: tofixedpointvar(10), z: tofixedpointvar(0) }),
+        (DebugSel::Final, Vector3 { x: tofixedpointvar(1), y: tofixedpointvar(12), z: tofixedpointvar(3) }),
+    ];
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    for (i, &(debug_sel, _)) in cases.iter().enumerate() {
+        for c in 0..POINT_PERIOD {
+            inputs.push(LidarInput {
+                valid: c == 0,
+                point,
+                rotation: rot_z90(),
+                translation: trans,
+                pose_write: i == 0 && c == 0,
+                pose_swap: i == 0 && c == 0,
+                debug_sel,
+                ..LidarInput::default()
+            });
+        }
+    }
+    for _ in 0..POINT_PERIOD {
+        inputs.push(LidarInput::default());
+    }
+
+    let results: Vec<Vector3> = simulate(cov, &uut, inputs)?
+        .into_iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| o.corrected_point)
+        .collect();
+
+    let expected: Vec<Vector3> = cases.iter().map(|&(_, v)| v).collect();
+    let ok = results == expected;
+    if !ok {
+        println!("EROARE: iesiri {:?}, asteptate {:?}", results, expected);
+    }
+
+    println!("Depanare (raw / rotated / final): {}", if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// contextele de pose: fiecare context k primeste T(10k, 0, 0) (contextul 2 si rotatia Z 90),
+// punctele P(1, 2, 3) aleg contextul prin ctx_id, iar ctx_id trebuie intors la iesire
+// in timpul punctului 3 contextul 0 e rescris cu T(-7, 0, 0); punctele pe alte contexte nu se schimba
+fn run_context_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = LidarProcessor::default();
+    let point = Vector3 { x: tofixedpointvar(1), y: tofixedpointvar(2), z: tofixedpointvar(3) };
+    let ctx_sequence = [3u8, 0, 2, 1, 1, 3, 0];
+    let rewrite_at = 3;
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    for k in 0..POSE_CONTEXTS as u8 {
+        inputs.push(LidarInput {
+            rotation: if k == 2 { rot_z90() } else { identity_rot() },
+            translation: Vector3 { x: tofixedpointvar(10 * k as i32), ..Vector3::default() },
+            pose_write: true,
+            pose_swap: true,
+            pose_ctx: bits(k as u128),
+            ..LidarInput::default()
+        });
+    }
+
+    let mut expected = Vec::new();
+    for (i, &ctx) in ctx_sequence.iter().enumerate() {
+        let t = if ctx == 0 && i > rewrite_at { -7 } else { 10 * ctx as i32 };
+        let corrected = if ctx == 2 {
+            Vector3 { x: tofixedpointvar(-2 + t), y: tofixedpointvar(1), z: tofixedpointvar(3) }
+        } else {
+            Vector3 { x: tofixedpointvar(1 + t), y: tofixedpointvar(2), z: tofixedpointvar(3) }
+        };
+        expected.push((corrected, ctx));
+
+        for c in 0..POINT_PERIOD {
+            let mut input = LidarInput { point, ctx_id: bits(ctx as u128), ..LidarInput::default() };
+            input.valid = c == 0;
+            if i == rewrite_at && c == 1 {
+                input.rotation = identity_rot();
+                input.translation = Vector3 { x: tofixedpointvar(-7), ..Vector3::default() };
+                input.pose_write = true;
+                input.pose_swap = true;
+                input.pose_ctx = bits(0);
+            }
+            inputs.push(input);
+        }
+    }
+    for _ in 0..POINT_PERIOD {
+        inputs.push(LidarInput::default());
+    }
+
+    let results: Vec<(Vector3, u8)> = simulate(cov, &uut, inputs)?
+        .into_iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| (o.corrected_point, o.ctx_id.raw() as u8))
+        .collect();
+
+    let ok = results == expected;
+    if !ok {
+        println!("EROARE: iesiri {:?}, asteptate {:?}", results, expected);
+    }
+
+    println!("Contexte de pose: {} puncte pe {} contexte, {}", results.len(), POSE_CONTEXTS, if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// modul cu viteze: omega = (0, 0, 0.5) rad/s, v = (2, 0, 0) m/s
+// dt = 0.25 s -> theta_z = 0.125, P(4, 0, 0) -> R*P = (4, 0.5, 0), T = (0.5, 0, 0) -> (4.5, 0.5, 0)
+// dt = 0 -> pose identitate, punctul iese neschimbat (toate valorile sunt exacte in 16.16 / Q2.16)
+fn run_velocity_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = LidarProcessor::default();
+    let frac = |num: i32, den: i32| s32::from((num as i128 * 65536) / den as i128);
+
+    let omega = Vector3 { x: frac(0, 1), y: frac(0, 1), z: frac(1, 2) };
+    let velocity = Vector3 { x: tofixedpointvar(2), y: frac(0, 1), z: frac(0, 1) };
+    let point = Vector3 { x: tofixedpointvar(4), y: frac(0, 1), z: frac(0, 1) };
+
+    let cases = [
+        (frac(1, 4), Vector3 { x: frac(9, 2), y: frac(1, 2), z: frac(0, 1) }),
+        (frac(0, 1), point),
+    ];
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    for &(dt, _) in cases.iter() {
+        for c in 0..POINT_PERIOD {
+            inputs.push(LidarInput {
+                valid: c == 0,
+                point,
+                velocity_mode: true,
+                omega,
+                velocity,
+                dt,
+                ..LidarInput::default()
+            });
+        }
+    }
+    for _ in 0..POINT_PERIOD {
+        inputs.push(LidarInput::default());
+    }
+
+    let results: Vec<Vector3> = simulate(cov, &uut, inputs)?
+        .into_iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| o.corrected_point)
+        .collect();
+
+    let expected: Vec<Vector3> = cases.iter().map(|&(_, v)| v).collect();
+    let ok = results == expected;
+    if !ok {
+        println!("EROARE: iesiri {:?}, asteptate {:?}", results, expected);
+    }
+
+    println!("Mod viteze (I + [omega*dt]x, v*dt): {}", if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// self-test-ul intern: tinem bist_enable activ si asteptam done + pass
+// fiecare vector are nevoie de ~5 cicluri (emitere + automat + comparatie)
+fn run_bist_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = LidarProcessor::default();
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    let enabled = LidarInput { bist_enable: true, ..LidarInput::default() };
+    for _ in 0..BIST_VECTORS * 8 {
+        inputs.push(enabled);
+    }
+
+    let cycles = simulate(cov, &uut, inputs)?;
+
+    let leaked = cycles.iter().filter(|(_, o)| o.valid).count();
+    let Some((_, last)) = cycles.last() else {
+        println!("EROARE: simularea nu a produs esantioane");
+        return Ok(false);
+    };
+    let bist = last.bist;
+
+    let ok = bist.done && bist.pass && !bist.fail && leaked == 0;
+    if bist.fail {
+        println!("EROARE: BIST a esuat la vectorul {:?}", bist.fail_index);
+    }
+    if !bist.done {
+        println!("EROARE: BIST nu s-a terminat");
+    }
+    if leaked > 0 {
+        println!("EROARE: {} rezultate de test au ajuns la iesire", leaked);
+    }
+
+    println!("BIST: {} vectori, {}", BIST_VECTORS, if ok { "PASS" } else { "FAIL" });
+    Ok(ok)
+}
+
+// ROM-ul si conditia de sfarsit din bist_kernel folosesc indexul ultimului vector ca literal
+// verificam ca ROM-ul are exact BIST_VECTORS intrari distincte (dupa ultima revine la vectorul implicit)
+// si ca automatul emite exact BIST_VECTORS vectori inainte de done (rezultatele nu conteaza aici)
+fn run_bist_rom_test() -> Result<bool, RHDLError> {
+    let rom: Vec<_> = (0..=BIST_VECTORS).map(|i| bist_rom(b4::from(i as u128))).collect();
+    let distinct = (1..BIST_VECTORS).all(|i| !rom[..i].contains(&rom[i]));
+    let rom_ok = distinct && rom[BIST_VECTORS] == rom[0];
+    if !rom_ok {
+        println!("EROARE: ROM-ul BIST nu are exact {} vectori distincti", BIST_VECTORS);
+    }
+
+    let uut = Bist::default();
+    let mut inputs = vec![BistInput::default(); 2];
+    let answering = BistInput { enable: true, result_valid: true, ..BistInput::default() };
+    for _ in 0..BIST_VECTORS * 4 {
+        inputs.push(answering);
+    }
+    let cycles = cycle_samples(uut.run(inputs.into_iter().with_reset(1).clock_pos_edge(100))?);
+    let issued = cycles.iter().filter(|(_, o)| o.drive.valid).count();
+    let done = cycles.last().is_some_and(|(_, o)| o.status.done);
+    let count_ok = done && issued == BIST_VECTORS;
+    if !count_ok {
+        println!("EROARE: BIST a emis {} vectori (done = {}), asteptati {}", issued, done, BIST_VECTORS);
+    }
+
+    let ok = rom_ok && count_ok;
+    println!("ROM BIST: {} vectori, {}", BIST_VECTORS, if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// testul adaptoarelor de magistrala: rotatia de 90 grade trimisa ca 16 cuvinte de 32 biti
+// raspunsul trebuie sa fie un pachet de 4 cuvinte cu y = 10.0 (0xA0000)
+fn run_bus_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = BusLidarProcessor::default();
+
+    let packet = LidarInput {
+        valid: true,
+        point: Vector3 { x: tofixedpointvar(10), y: tofixedpointvar(0), z: tofixedpointvar(0) },
+        rotation: rot_z90(),
+        translation: Vector3::default(),
+        pose_write: true,
+        pose_swap: true,
+        ..LidarInput::default()
+    };
+
+    let mut inputs = vec![BusWord32::default(); 2];
+    for index in 0..INPUT_WORDS {
+        inputs.push(BusWord32 {
+            valid: true,
+            sof: index == 0,
+            data: input_word(packet, b5::from(index as u128)),
+        });
+    }
+    for _ in 0..OUTPUT_WORDS + POINT_PERIOD + 2 {
+        inputs.push(BusWord32::default());
+    }
+
+    let cycles = simulate(cov, &uut, inputs)?;
+    let words: Vec<u128> = cycles.iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| o.data.raw())
+        .collect();
+
+    let expected = [1, 0, 0xA0000, 0];
+    let ok = words == expected;
+    if !ok {
+        println!("EROARE: cuvinte primite {:X?}, asteptate {:X?}", words, expected);
+    }
+
+    println!("Magistrala 32 biti: {} cuvinte intrare, {} cuvinte iesire, {}", INPUT_WORDS, words.len(), if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// adaptoarele de 64 biti, fiecare separat: deserializatorul trebuie sa reasambleze exact pachetul
+// trimis in 8 beat-uri, iar serializatorul sa scoata 2 beat-uri cu cuvintele din output_word (2k jos, 2k+1 sus)
+fn run_bus64_test() -> Result<bool, RHDLError> {
+    let mut ok = true;
+
+    // toate campurile din header si valori diferite in fiecare cuvant, ca o inversare sa se vada
+    let packet = LidarInput {
+        valid: true,
+        point: Vector3 { x: tofixedpointvar(-7), y: s32::from(0x1234_5678i128), z: s32::from(-1i128) },
+        rotation: rot_z90(),
+        translation: Vector3 { x: tofixedpointvar(3), y: tofixedpointvar(-4), z: s32::from(12345i128) },
+        pose_write: true,
+        pose_swap: true,
+        first: true,
+        pose_compose: true,
+        debug_sel: DebugSel::Rotated,
+        ctx_id: b2::from(2u128),
+        pose_ctx: b2::from(1u128),
+        ..LidarInput::default()
+    };
+    let in_word = |index: usize| input_word(packet, b5::from(index as u128)).raw();
+
+    let mut beats = vec![BusWord64::default(); 2];
+    for k in 0..INPUT_WORDS / 2 {
+        beats.push(BusWord64 {
+            valid: true,
+            sof: k == 0,
+            data: b64::from(in_word(2 * k) | (in_word(2 * k + 1) << 32)),
+        });
+    }
+    beats.push(BusWord64::default());
+
+    let rx = WordDeserializer64::default();
+    let cycles = cycle_samples(rx.run(beats.into_iter().with_reset(1).clock_pos_edge(100))?);
+    let assembled: Vec<LidarInput> = cycles.iter().map(|(_, o)| *o).filter(|o| o.valid).collect();
+    if assembled != [packet] {
+        println!("EROARE: deserializatorul a asamblat {:?}, asteptat {:?}", assembled, packet);
+        ok = false;
+    }
+
+    let result = LidarOutput {
+        valid: true,
+        corrected_point: Vector3 { x: tofixedpointvar(1), y: tofixedpointvar(-2), z: s32::from(77i128) },
+        ctx_id: b2::from(3u128),
+        last: true,
+        scan_done: true,
+        scan_count: b32::from(5u128),
+        ..LidarOutput::default()
+    };
+    let out_word = |index: usize| output_word(result, b3::from(index as u128)).raw();
+
+    let mut outputs = vec![LidarOutput::default(); 2];
+    outputs.push(result);
+    for _ in 0..OUTPUT_WORDS {
+        outputs.push(LidarOutput::default());
+    }
+
+    let tx = WordSerializer64::default();
+    let cycles = cycle_samples(tx.run(outputs.into_iter().with_reset(1).clock_pos_edge(100))?);
+    let sent: Vec<(bool, u128)> = cycles.iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| (o.sof, o.data.raw()))
+        .collect();
+    let expected: Vec<(bool, u128)> = (0..OUTPUT_WORDS / 2)
+        .map(|k| (k == 0, out_word(2 * k) | (out_word(2 * k + 1) << 32)))
+        .collect();
+    if sent != expected {
+        println!("EROARE: beat-uri (sof, date) primite {:X?}, asteptate {:X?}", sent, expected);
+        ok = false;
+    }
+
+    println!("Magistrala 64 biti: {} beat-uri intrare, {} beat-uri iesire, {}",
+             INPUT_WORDS / 2, sent.len(), if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// marcajele de scanare: doua scanari (3 + 2 puncte), scan_done trebuie sa apara odata cu
+// ultimul punct al fiecareia, cu numarul corect de puncte
+fn run_scan_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let uut = LidarProcessor::default();
+    let scans = [3usize, 2];
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    for (s, &len) in scans.iter().enumerate() {
+        for i in 0..len {
+            let point = Vector3 { x: tofixedpointvar((10 * s + i) as i32), ..Vector3::default() };
+            for c in 0..POINT_PERIOD {
+                inputs.push(LidarInput {
+                    valid: c == 0,
+                    point,
+                    rotation: identity_rot(),
+                    pose_write: c == 0,
+                    pose_swap: c == 0,
+                    first: i == 0,
+                    last: i + 1 == len,
+                    ..LidarInput::default()
+                });
+            }
+        }
+    }
+    for _ in 0..POINT_PERIOD {
+        inputs.push(LidarInput::default());
+    }
+
+    let cycles = simulate(cov, &uut, inputs)?;
+
+    let outputs: Vec<LidarOutput> = cycles.iter().map(|(_, o)| *o).filter(|o| o.valid).collect();
+    let done_counts: Vec<u128> = cycles.iter()
+        .filter(|(_, o)| o.scan_done)
+        .map(|(_, o)| o.scan_count.raw())
+        .collect();
+
+    let mut ok = true;
+    let firsts = outputs.iter().filter(|o| o.first).count();
+    let lasts = outputs.iter().filter(|o| o.last).count();
+    if firsts != scans.len() || lasts != scans.len() {
+        println!("EROARE: first/last nu s-au propagat ({} first, {} last)", firsts, lasts);
+        ok = false;
+    }
+    if outputs.iter().any(|o| o.scan_done != o.last) {
+        println!("EROARE: scan_done nu coincide cu ultimul punct");
+        ok = false;
+    }
+    let expected: Vec<u128> = scans.iter().map(|&n| n as u128).collect();
+    if done_counts != expected {
+        println!("EROARE: numar de puncte pe scanare {:?}, asteptat {:?}", done_counts, expected);
+        ok = false;
+    }
+
+    println!("Scanari: {} scan_done, {}", done_counts.len(), if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// proiectia in range image cu tabelul VLP-16 (+15 .. -15 grade, pas 2) si 1024 de coloane
+// punctele sunt puse la mijlocul unei coloane si pe elevatia unui fascicul, ca CORDIC-ul sa nu cada pe o granita
+fn run_range_image_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let columns = 1024;
+    let uut = LidarProcessor::new(RangeImageConfig::vlp16(columns));
+
+    // (coloana, rand, distanta in metri)
+    let cases = [(0usize, 7usize, 10.0f64), (100, 0, 25.5), (511, 15, 3.25), (700, 9, 60.0), (1023, 4, 1.5)];
+
+    let mut inputs = vec![LidarInput::default(); 2];
+    for (i, &(col, row, range)) in cases.iter().enumerate() {
+        let azimuth = (col as f64 + 0.5) / columns as f64 * std::f64::consts::TAU;
+        let elevation = (15.0 - 2.0 * row as f64).to_radians();
+        let to_fix = |v: f64| s32::from((v * 65536.0).round() as i128);
+        let point = Vector3 {
+            x: to_fix(range * elevation.cos() * azimuth.cos()),
+            y: to_fix(range * elevation.cos() * azimuth.sin()),
+            z: to_fix(range * elevation.sin()),
+        };
+
+        for c in 0..POINT_PERIOD {
+            inputs.push(LidarInput {
+                valid: c == 0,
+                point,
+                rotation: identity_rot(),
+                pose_write: i == 0 && c == 0,
+                pose_swap: i == 0 && c == 0,
+                ..LidarInput::default()
+            });
+        }
+    }
+    for _ in 0..2 * POINT_PERIOD {
+        inputs.push(LidarInput::default());
+    }
+
+    let outputs: Vec<LidarOutput> = simulate(cov, &uut, inputs)?
+        .into_iter()
+        .map(|(_, o)| o)
+        .filter(|o| o.valid)
+        .collect();
+
+    let mut ok = outputs.len() == cases.len();
+    if !ok {
+        println!("EROARE: {} puncte proiectate, asteptate {}", outputs.len(), cases.len());
+    }
+    for (out, &(col, row, range)) in outputs.iter().zip(cases.iter()) {
+        let pixel = out.pixel;
+        let got_range = pixel.range.typed_bits().as_i64().unwrap() as f64 / 65536.0;
+        // trunchierile din cele 2 x 16 iteratii CORDIC raman sub 2 mm
+        if !pixel.valid || pixel.row.raw() as usize != row || pixel.column.raw() as usize != col
+            || (got_range - range).abs() > 0.002 {
+            println!("EROARE: pixel {:?}, asteptat rand {}, coloana {}, distanta {}", pixel, row, col, range);
+            ok = false;
+        }
+    }
+
+    println!("Range image: {} pixeli, {}", outputs.len(), if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// etajul de formatare: coordonatele rotunjite la mm / cm, saturate pe 16 biti
+// referinta e calculata in f64: floor(v * scala + 0.5), apoi limitata la [-32768, 32767] pe 16 biti
+fn run_format_test(cov: &mut Coverage) -> Result<bool, RHDLError> {
+    let frac = |num: i64, den: i64| s32::from((num as i128 * 65536) / den as i128);
+    // (punct, translatie): -1000 m nu incape in portul de 256 m al rotatiei, deci vine din translatie
+    let cases = [
+        (Vector3 { x: frac(1234567, 1000000), y: frac(-5, 2), z: frac(-1, 2000) }, Vector3::default()),
+        (Vector3 { x: frac(40, 1), y: frac(-40, 1), z: frac(3, 1000) }, Vector3::default()),
+        (Vector3 { x: frac(-200, 1), y: frac(1005, 100), z: s32::from(1i128) }, Vector3 { x: frac(-800, 1), ..Vector3::default() }),
+    ];
+    let points: Vec<Vector3> = cases.iter()
+        .map(|(p, t)| Vector3 { x: p.x + t.x, y: p.y + t.y, z: p.z + t.z })
+        .collect();
+    let configs = [
+        OutputFormat::new(CompactUnit::Millimetre, true),
+        OutputFormat::new(CompactUnit::Millimetre, false),
+        OutputFormat::new(CompactUnit::Centimetre, false),
+    ];
+
+    // coordonatele intregi impachetate in CompactPoint.data
+    let unpack = |c: &CompactPoint| -> [i64; 3] {
+        let data = c.data.raw();
+        if c.wide {
+            [0, 32, 64].map(|s| (data >> s) as u32 as i32 as i64)
+        } else {
+            [0, 16, 32].map(|s| (data >> s) as u16 as i16 as i64)
+        }
+    };
+
+    let mut ok = true;
+    for cfg in configs {
+        let uut = LidarProcessor::default().with_output_format(cfg);
+
+        let mut inputs = vec![LidarInput::default(); 2];
+        for &(point, translation) in &cases {
+            for c in 0..POINT_PERIOD {
+                inputs.push(LidarInput {
+                    valid: c == 0,
+                    point,
+                    rotation: identity_rot(),
+                    translation,
+                    pose_write: c == 0,
+                    pose_swap: c == 0,
+                    ..LidarInput::default()
+                });
+            }
+        }
+        for _ in 0..POINT_PERIOD {
+            inputs.push(LidarInput::default());
+        }
+
+        let outputs: Vec<LidarOutput> = simulate(cov, &uut, inputs)?
+            .into_iter()
+            .map(|(_, o)| o)
+            .filter(|o| o.valid)
+            .collect();
+
+        let scale = match cfg.unit {
+            CompactUnit::Millimetre => 1000.0,
+            CompactUnit::Centimetre => 100.0,
+        };
+        let expected: Vec<[i64; 3]> = points.iter()
+            .map(|p| [p.x, p.y, p.z].map(|v| {
+                let units = (lsb(v) / 65536.0 * scale + 0.5).floor() as i64;
+                if cfg.wide { units } else { units.clamp(-32768, 32767) }
+            }))
+            .collect();
+        let results: Vec<[i64; 3]> = outputs.iter().map(|o| unpack(&o.compact)).collect();
+
+        let flags_ok = outputs.iter().all(|o| o.compact.valid && o.compact.wide == cfg.wide && o.compact.unit == cfg.unit);
+        let cfg_ok = flags_ok && results == expected;
+        if !cfg_ok {
+            println!("EROARE: {:?}: {:?}, asteptate {:?}", cfg, results, expected);
+        }
+        ok &= cfg_ok;
+    }
+
+    println!("Format compact (mm32, mm16, cm16): {}", if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// operanzi pseudo-aleatori (LCG simplu), ca testul sa fie reproductibil
+fn test_operands(count: usize) -> Vec<(s32, s32)> {
+    // cazurile de la margine intai
+    let corners = [0i32, 1, -1, 0x10000, -0x10000, 0xFFFF, -0xFFFF, i32::MAX, i32::MIN, 0x7FFF_0000, -0x8000];
+    let mut pairs = Vec::new();
+    for &a in &corners {
+        for &b in &corners {
+            pairs.push((a, b));
+        }
+    }
+
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    while pairs.len() < count {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let a = (state >> 32) as i32;
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let b = (state >> 32) as i32;
+        pairs.push((a, b));
+    }
+
+    pairs.into_iter()
+        .map(|(a, b)| (s32::from(a as i128), s32::from(b as i128)))
+        .collect()
+}
+
+// testul cycle-accurate al inmultitorului DSP fata de fixed_mul (referinta combinationala)
+// operanzii intra back-to-back, iesirile trebuie sa vina in ordine, fara goluri, dupa exact 'latency' cicluri
+fn run_multiplier_test(cfg: MulPipeline) -> Result<bool, RHDLError> {
+    let latency = cfg.latency();
+    let uut = DspMul::new(cfg);
+    let operands = test_operands(256);
+
+    let mut inputs = vec![MulInput::default(); 2];
+    for &(a, b) in &operands {
+        inputs.push(MulInput { valid: true, a, b });
+    }
+    // golim pipeline-ul
+    for _ in 0..latency + 4 {
+        inputs.push(MulInput::default());
+    }
+
+    let stream = inputs.into_iter().with_reset(1).clock_pos_edge(100);
+    let cycles = cycle_samples(uut.run(stream)?);
+
+    let first_in = cycles.iter().position(|(i, _)| i.valid);
+    let first_out = cycles.iter().position(|(_, o)| o.valid);
+    let results: Vec<s32> = cycles.iter()
+        .filter(|(_, o)| o.valid)
+        .map(|(_, o)| o.result)
+        .collect();
+
+    let mut ok = true;
+    match (first_in, first_out) {
+        (Some(i), Some(o)) if o - i == latency => {},
+        (i, o) => {
+            println!("EROARE: latenta masurata {:?} -> {:?}, asteptata {}", i, o, latency);
+            ok = false;
+        }
+    }
+
+    // iesirile valide trebuie sa fie consecutive (throughput de 1 / ciclu)
+    if let Some(o) = first_out {
+        if cycles[o..o + results.len()].iter().any(|(_, out)| !out.valid) {
+            println!("EROARE: goluri in fluxul de iesire");
+            ok = false;
+        }
+    }
+
+    if results.len() != operands.len() {
+        println!("EROARE: {} rezultate pentru {} operanzi", results.len(), operands.len());
+        ok = false;
+    }
+
+    let mismatches = operands.iter()
+        .zip(&results)
+        .filter(|&(&(a, b), &r)| fixed_mul(a, b) != r)
+        .count();
+    if mismatches > 0 {
+        println!("EROARE: {} rezultate diferite de fixed_mul", mismatches);
+        ok = false;
+    }
+
+    println!("DspMul latenta {}: {} operanzi, {}", latency, operands.len(), if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// valoarea reala a unui numar 16.16, in LSB (2^-16)
+fn lsb(v: s32) -> f64 {
+    v.typed_bits().as_i64().unwrap() as f64
+}
+
+// operanzii pentru sweep-uri: perechile din test_operands + puteri ale lui 2 si vecinii lor (toate magnitudinile)
+fn sweep_operands() -> Vec<(s32, s32)> {
+    let mut values: Vec<i32> = Vec::new();
+    for k in 0..31 {
+        let p = 1i32 << k;
+        values.extend([p, p - 1, p + 1, -p, -(p + 1)]);
+    }
+    let mut pairs = test_operands(2048);
+    for &a in &values {
+        for &b in values.iter().step_by(7) {
+            pairs.push((s32::from(a as i128), s32::from(b as i128)));
+        }
+    }
+    pairs
+}
+
+// cel mai mare numar reprezentabil, in LSB
+const FIX_MAX: f64 = 2147483647.0;
+
+// impartire, reciproc, radacina, 1/sqrt, norma si normalizare fata de f64
+// toleranta: 1 LSB pentru operatiile trunchiate, iar 1/sqrt si normalizarea mostenesc eroarea radacinii
+fn run_alu_sweep_test() -> bool {
+    let operands = sweep_operands();
+    let mut failures = Vec::new();
+
+    for &(a, b) in &operands {
+        let (fa, fb) = (lsb(a), lsb(b));
+
+        // impartirea: catul trunchiat sau saturat
+        let div_ref = if fb == 0.0 {
+            if fa < 0.0 { -FIX_MAX } else { FIX_MAX }
+        } else {
+            (fa / fb * 65536.0).clamp(-FIX_MAX, FIX_MAX)
+        };
+        if (lsb(fixed_div(a, b)) - div_ref).abs() >= 1.0 {
+            failures.push(format!("div {} / {}", fa, fb));
+        }
+
+        let recip_ref = if fb == 0.0 { FIX_MAX } else { (65536.0 * 65536.0 / fb).clamp(-FIX_MAX, FIX_MAX) };
+        if (lsb(fixed_recip(b)) - recip_ref).abs() >= 1.0 {
+            failures.push(format!("recip {}", fb));
+        }
+
+        // radacina: valorile negative dau 0
+        let sqrt_ref = if fa > 0.0 { (fa * 65536.0).sqrt() } else { 0.0 };
+        if (lsb(fixed_sqrt(a)) - sqrt_ref).abs() >= 1.0 {
+            failures.push(format!("sqrt {}", fa));
+        }
+
+        // 1/sqrt: eroarea relativa a radacinii (sub 1 LSB) se transmite in rezultat
+        if fa > 0.0 {
+            let rsqrt_ref = (65536.0 * 65536.0 / sqrt_ref).min(FIX_MAX);
+            let tol = rsqrt_ref / (sqrt_ref - 1.0) + 1.0;
+            if (lsb(fixed_rsqrt(a)) - rsqrt_ref).abs() > tol {
+                failures.push(format!("rsqrt {}", fa));
+            }
+        }
+
+        // norma si normalizarea pe vectorul (a, b, a / 2)
+        let c = s32::from((a.typed_bits().as_i64().unwrap() / 2) as i128);
+        let v = Vector3 { x: a, y: b, z: c };
+        let norm_ref = (fa * fa + fb * fb + lsb(c) * lsb(c)).sqrt().min(FIX_MAX);
+        let norm = vector_norm(v);
+        if (lsb(norm) - norm_ref).abs() >= 1.0 {
+            failures.push(format!("norm ({}, {}, {})", fa, fb, lsb(c)));
+        }
+
+        // normalizarea doar pentru vectori de cel putin 1.0 (sub 1.0 norma are prea putini biti)
+        if norm_ref >= 65536.0 && norm_ref < FIX_MAX {
+            let unit = vector_normalize(v);
+            let got = [lsb(unit.x), lsb(unit.y), lsb(unit.z)];
+            let want = [fa, fb, lsb(c)].map(|x| x / norm_ref * 65536.0);
+            if got.iter().zip(want.iter()).any(|(g, w)| (g - w).abs() > 2.0) {
+                failures.push(format!("normalize ({}, {}, {})", fa, fb, lsb(c)));
+            }
+        }
+    }
+
+    for f in failures.iter().take(10) {
+        println!("EROARE: {}", f);
+    }
+    let ok = failures.is_empty();
+    println!("ALU impartire/radacina: {} operanzi, {} erori, {}", operands.len(), failures.len(), if ok { "OK" } else { "ESEC" });
+    ok
+}
+
+// unitatea secventiala: rezultatul fata de kernelul combinational (la precizie completa e identic)
+// sau fata de f64 (cu mai putini biti de fractie), plus latenta masurata fata de IterConfig::latency
+fn run_iterative_test(cfg: IterConfig) -> Result<bool, RHDLError> {
+    let frac = cfg.frac_bits.raw() as u32;
+    let uut = IterativeUnit::new(cfg);
+    let operands: Vec<(IterOp, s32, s32)> = test_operands(96)
+        .into_iter()
+        .flat_map(|(a, b)| [(IterOp::Div, a, b), (IterOp::Sqrt, a, b), (IterOp::Recip, a, b), (IterOp::Rsqrt, a, b)])
+        .collect();
+
+    // o operatie la un moment dat: start, apoi asteptam exact latenta
+    let mut inputs = vec![IterInput::default(); 2];
+    for &(op, a, b) in &operands {
+        inputs.push(IterInput { start: true, op, a, b });
+        for _ in 0..cfg.latency(op) {
+            inputs.push(IterInput::default());
+        }
+    }
+
+    let stream = inputs.into_iter().with_reset(1).clock_pos_edge(100);
+    let cycles = cycle_samples(uut.run(stream)?);
+
+    let starts: Vec<usize> = cycles.iter().enumerate().filter(|(_, (i, _))| i.start).map(|(k, _)| k).collect();
+    let dones: Vec<(usize, s32)> = cycles.iter().enumerate()
+        .filter(|(_, (_, o))| o.valid)
+        .map(|(k, (_, o))| (k, o.result))
+        .collect();
+
+    let mut ok = dones.len() == operands.len();
+    if !ok {
+        println!("EROARE: {} rezultate pentru {} operatii", dones.len(), operands.len());
+    }
+
+    // cu mai putini biti de fractie rezultatul e trunchiat la 2^(16 - frac) LSB;
+    // la 1/sqrt reciprocul se face din radacina deja trunchiata, deci referinta trunchiaza la fel
+    let tol = (1u32 << (16 - frac)) as f64;
+    let truncate = |v: s32| s32::from((lsb(v) as i64 & !((1i64 << (16 - frac)) - 1)) as i128);
+    let mut errors = 0;
+    for ((&(op, a, b), &start), &(done, result)) in operands.iter().zip(&starts).zip(&dones) {
+        if done - start != cfg.latency(op) {
+            println!("EROARE: latenta {} pentru {:?}, asteptata {}", done - start, op, cfg.latency(op));
+            ok = false;
+        }
+        let reference = match op {
+            IterOp::Div => fixed_div(a, b),
+            IterOp::Sqrt => fixed_sqrt(a),
+            IterOp::Recip => fixed_recip(b),
+            IterOp::Rsqrt => fixed_recip(truncate(fixed_sqrt(a))),
+        };
+        let bad = if frac == 16 {
+            result != reference
+        } else {
+            (lsb(result) - lsb(reference)).abs() >= tol
+        };
+        if bad {
+            errors += 1;
+        }
+    }
+    if errors > 0 {
+        println!("EROARE: {} rezultate gresite", errors);
+        ok = false;
+    }
+
+    println!("IterativeUnit (dublu {}, {} biti fractie): latenta div {}, sqrt {}, recip {}, rsqrt {}, {}",
+             cfg.double_step, frac, cfg.latency(IterOp::Div), cfg.latency(IterOp::Sqrt),
+             cfg.latency(IterOp::Recip), cfg.latency(IterOp::Rsqrt), if ok { "OK" } else { "ESEC" });
+    Ok(ok)
+}
+
+// rezultatul unui test: o eroare de simulare sau o verificare picata il trec in lista de esecuri
+fn check(failed: &mut Vec<String>, name: &str, result: Result<bool, RHDLError>) {
+    match result {
+        Ok(true) => {}
+        Ok(false) => failed.push(name.to_string()),
+        Err(e) => {
+            println!("{}", miette_report(e));
+            failed.push(name.to_string());
+        }
+    }
+}
+
 fn main() {
+    // acoperirea se aduna peste toate testele care ruleaza LidarProcessor
+    let mut cov = Coverage::new();
+    let mut failed = Vec::new();
+
     // testam rotatia de 90 grade pe z: input (10, 0, 0) -> output (0, 10, 0)
     // in fixed-point, 10.0 = 655360, adica 0xA0000 in hexa
     // in gtkwave cautam secventa 'A0000' in semnalul 'temp_rotated' cand valid=1
     // in ierarhie, cautam top/engine/temp_rotated/dff unde dff reprezinta x,y,z
     // iar y trebuie sa aiba valoarea 0xA0000
-    if let Err(e) = run_simulation() {
-        println!("{}", miette_report(e));
+    check(&mut failed, "simulare", run_simulation(&mut cov).map(|_| true));
+
+    println!("--- Test pose dublat (shadow + swap) ---");
+    check(&mut failed, "pose dublat", run_double_buffer_test(&mut cov));
+
+    println!("--- Test compunere pose (Matrix4x4) ---");
+    check(&mut failed, "compunere pose", run_compose_test(&mut cov));
+
+    println!("--- Test selectie depanare ---");
+    check(&mut failed, "selectie depanare", run_debug_test(&mut cov));
+
+    println!("--- Test contexte de pose ---");
+    check(&mut failed, "contexte de pose", run_context_test(&mut cov));
+
+    println!("--- Test mod viteze ---");
+    check(&mut failed, "mod viteze", run_velocity_test(&mut cov));
+
+    println!("--- Test BIST ---");
+    check(&mut failed, "BIST", run_bist_test(&mut cov));
+    check(&mut failed, "ROM BIST", run_bist_rom_test());
+
+    println!("--- Test magistrala 32 biti ---");
+    check(&mut failed, "magistrala 32 biti", run_bus_test(&mut cov));
+
+    println!("--- Test magistrala 64 biti ---");
+    check(&mut failed, "magistrala 64 biti", run_bus64_test());
+
+    println!("--- Test marcaje de scanare ---");
+    check(&mut failed, "marcaje de scanare", run_scan_test(&mut cov));
+
+    println!("--- Test proiectie range image ---");
+    check(&mut failed, "proiectie range image", run_range_image_test(&mut cov));
+
+    println!("--- Test format compact ---");
+    check(&mut failed, "format compact", run_format_test(&mut cov));
+
+    // inmultitorul descompus pe DSP-uri, in configuratia completa si fara registre
+    println!("--- Test DspMul vs fixed_mul ---");
+    for cfg in [MulPipeline::full(), MulPipeline::combinational()] {
+        check(&mut failed, &format!("DspMul {:?}", cfg), run_multiplier_test(cfg));
+    }
+
+    println!("--- Test impartire / radacina (combinational vs f64) ---");
+    check(&mut failed, "impartire / radacina", Ok(run_alu_sweep_test()));
+
+    println!("--- Test unitate iterativa ---");
+    for cfg in [IterConfig::full(), IterConfig::fast(), IterConfig::fast().with_frac_bits(8)] {
+        check(&mut failed, &format!("IterativeUnit {:?}", cfg), run_iterative_test(cfg));
     }
-}
\ No newline at end of file
+
+    println!("--- Acoperire automat + engine ---");
+    cov.print_summary();
+
+    // codul de iesire spune daca testbench-ul a trecut (pentru CI / scripturi)
+    if !failed.is_empty() {
+        println!("ESEC: {} teste picate: {}", failed.len(), failed.join(", "));
+        std::process::exit(1);
+    }
+    println!("Toate testele au trecut");
+}
@@ -0,0 +1,199 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::constant::Constant;
+use rhdl_fpga::core::dff::DFF;
+use crate::types::*;
+
+// inmultitor 32x32 spart in produse partiale de marimea unui DSP (18x18 cu semn)
+// a = a_hi * 2^16 + a_lo, unde a_hi are semn (16 biti) si a_lo e fara semn (16 biti)
+// a * b = (a_hi*b_hi << 32) + ((a_hi*b_lo + a_lo*b_hi) << 16) + a_lo*b_lo
+// fiecare produs partial incape intr-o felie DSP: a_lo/b_lo sunt extinse la 17 biti cu semn (mereu pozitivi)
+
+// operanzii despartiti in jumatati
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct MulOperands {
+    pub a_hi: s16,
+    pub a_lo: s17,
+    pub b_hi: s16,
+    pub b_lo: s17,
+}
+
+// cele 4 produse partiale, fiecare iesire a unui DSP
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct PartialProducts {
+    pub hh: s32,
+    pub hl: s34,
+    pub lh: s34,
+    pub ll: s34,
+}
+
+// configurarea registrelor de pipeline
+// fiecare etaj poate fi activat sau ocolit, sinteza elimina registrele ocolite
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct MulPipeline {
+    pub reg_operands: bool, // registru pe intrari (inainte de DSP)
+    pub reg_partials: bool, // registru pe iesirea DSP-urilor (MREG)
+    pub reg_result: bool,   // registru dupa arborele de sumare (PREG)
+}
+
+impl MulPipeline {
+    // toate registrele active, pentru frecvente mari
+    pub fn full() -> Self {
+        Self { reg_operands: true, reg_partials: true, reg_result: true }
+    }
+
+    // fara registre, echivalent cu fixed_mul
+    pub fn combinational() -> Self {
+        Self::default()
+    }
+
+    // numarul de cicluri intre intrare si rezultat
+    pub fn latency(&self) -> usize {
+        self.reg_operands as usize + self.reg_partials as usize + self.reg_result as usize
+    }
+}
+
+// interfata io
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct MulInput {
+    pub valid: bool,
+    pub a: Fixed32,
+    pub b: Fixed32,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct MulOutput {
+    pub valid: bool,
+    pub result: Fixed32,
+}
+
+// date intre etaje (valid-ul merge in paralel cu datele)
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct PartialStage {
+    pub valid: bool,
+    pub pp: PartialProducts,
+}
+
+// despartim operanzii in jumatati
+#[kernel]
+pub fn split_operands(a: Fixed32, b: Fixed32) -> MulOperands {
+    let a_hi: s16 = (a >> 16).resize();
+    let b_hi: s16 = (b >> 16).resize();
+
+    // partea de jos e fara semn, o extindem cu un 0 ca sa ramana pozitiva
+    let a_lo: b17 = (a.as_unsigned() & bits(0xFFFF)).resize();
+    let b_lo: b17 = (b.as_unsigned() & bits(0xFFFF)).resize();
+
+    MulOperands {
+        a_hi,
+        a_lo: a_lo.as_signed(),
+        b_hi,
+        b_lo: b_lo.as_signed(),
+    }
+}
+
+// cele 4 inmultiri, fiecare pe cate un DSP
+#[kernel]
+pub fn partial_products(ops: MulOperands) -> PartialProducts {
+    let a_hi_w: s34 = ops.a_hi.resize();
+    let b_hi_w: s34 = ops.b_hi.resize();
+    let a_lo_w: s34 = ops.a_lo.resize();
+    let b_lo_w: s34 = ops.b_lo.resize();
+
+    let hh: s32 = (a_hi_w * b_hi_w).resize();
+
+    PartialProducts {
+        hh,
+        hl: a_hi_w * b_lo_w,
+        lh: a_lo_w * b_hi_w,
+        ll: a_lo_w * b_lo_w,
+    }
+}
+
+// arborele de sumare + shiftarea cu 16 (la fel ca fixed_mul)
+#[kernel]
+pub fn sum_partials(pp: PartialProducts) -> Fixed32 {
+    let hh: s64 = pp.hh.resize();
+    let hl: s64 = pp.hl.resize();
+    let lh: s64 = pp.lh.resize();
+    let ll: s64 = pp.ll.resize();
+
+    let prod = (hh << 32) + ((hl + lh) << 16) + ll;
+
+    (prod >> 16).resize()
+}
+
+// referinta combinationala a descompunerii, trebuie sa dea exact fixed_mul
+#[kernel]
+pub fn fixed_mul_dsp(a: Fixed32, b: Fixed32) -> Fixed32 {
+    sum_partials(partial_products(split_operands(a, b)))
+}
+
+#[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+pub struct DspMul {
+    cfg: Constant<MulPipeline>,
+    operands: DFF<MulInput>,
+    partials: DFF<PartialStage>,
+    result: DFF<MulOutput>,
+}
+
+impl DspMul {
+    pub fn new(cfg: MulPipeline) -> Self {
+        Self {
+            cfg: Constant::new(cfg),
+            operands: DFF::new(MulInput::default()),
+            partials: DFF::new(PartialStage::default()),
+            result: DFF::new(MulOutput::default()),
+        }
+    }
+}
+
+impl Default for DspMul {
+    fn default() -> Self {
+        Self::new(MulPipeline::full())
+    }
+}
+
+// interfata io
+impl SynchronousIO for DspMul {
+    type I = MulInput;
+    type O = MulOutput;
+    type Kernel = dsp_mul_kernel;
+}
+
+#[kernel]
+pub fn dsp_mul_kernel(_cr: ClockReset, input: MulInput, q: Q) -> (MulOutput, D) {
+    let cfg = q.cfg;
+
+    // etaj 0: operanzii (din registru sau direct de la intrare)
+    let mut stage0 = input;
+    if cfg.reg_operands {
+        stage0 = q.operands;
+    }
+
+    // etaj 1: produsele partiale
+    let partials_now = PartialStage {
+        valid: stage0.valid,
+        pp: partial_products(split_operands(stage0.a, stage0.b)),
+    };
+    let mut stage1 = partials_now;
+    if cfg.reg_partials {
+        stage1 = q.partials;
+    }
+
+    // etaj 2: suma finala
+    let result_now = MulOutput {
+        valid: stage1.valid,
+        result: sum_partials(stage1.pp),
+    };
+    let mut output = result_now;
+    if cfg.reg_result {
+        output = q.result;
+    }
+
+    (output, D {
+        cfg: (),
+        operands: input,
+        partials: partials_now,
+        result: result_now,
+    })
+}
@@ -0,0 +1,92 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::dff::DFF;
+use crate::types::PerfStatus;
+
+// evenimentele observate in fiecare ciclu
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct PerfEvents {
+    pub valid_in: bool, // host-ul a trimis un punct
+    pub busy: bool,     // automatul nu e in Idle (punctul trimis acum se pierde)
+    pub emitted: bool,  // a iesit un punct corectat
+}
+
+// contoare de performanta free-running
+// toate au 32 de biti si se reiau de la 0 la overflow (~43 s la 100 MHz pentru cycles)
+#[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+pub struct PerfCounters {
+    cycles: DFF<b32>,
+    accepted: DFF<b32>,
+    emitted: DFF<b32>,
+    dropped: DFF<b32>,
+    busy_cycles: DFF<b32>,
+    // cicluri de la acceptarea punctului curent
+    latency: DFF<b16>,
+    max_latency: DFF<b16>,
+}
+
+impl Default for PerfCounters {
+    fn default() -> Self {
+        Self {
+            cycles: DFF::new(b32::default()),
+            accepted: DFF::new(b32::default()),
+            emitted: DFF::new(b32::default()),
+            dropped: DFF::new(b32::default()),
+            busy_cycles: DFF::new(b32::default()),
+            latency: DFF::new(b16::default()),
+            max_latency: DFF::new(b16::default()),
+        }
+    }
+}
+
+// interfata io
+impl SynchronousIO for PerfCounters {
+    type I = PerfEvents;
+    type O = PerfStatus;
+    type Kernel = perf_kernel;
+}
+
+#[kernel]
+pub fn perf_kernel(_cr: ClockReset, ev: PerfEvents, q: Q) -> (PerfStatus, D) {
+    let mut d = D {
+        cycles: q.cycles + bits(1),
+        accepted: q.accepted,
+        emitted: q.emitted,
+        dropped: q.dropped,
+        busy_cycles: q.busy_cycles,
+        latency: q.latency,
+        max_latency: q.max_latency,
+    };
+
+    // punctul e acceptat doar daca automatul e liber
+    if ev.valid_in && !ev.busy {
+        d.accepted = q.accepted + bits(1);
+        d.latency = bits(1);
+    }
+    if ev.valid_in && ev.busy {
+        d.dropped = q.dropped + bits(1);
+    }
+
+    if ev.busy {
+        d.busy_cycles = q.busy_cycles + bits(1);
+        d.latency = q.latency + bits(1);
+    }
+
+    // la iesire comparam latenta punctului cu maximul
+    if ev.emitted {
+        d.emitted = q.emitted + bits(1);
+        if q.latency > q.max_latency {
+            d.max_latency = q.latency;
+        }
+    }
+
+    let status = PerfStatus {
+        cycles: q.cycles,
+        accepted: q.accepted,
+        emitted: q.emitted,
+        dropped: q.dropped,
+        busy_cycles: q.busy_cycles,
+        max_latency: q.max_latency,
+    };
+
+    (status, d)
+}
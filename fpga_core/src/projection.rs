@@ -0,0 +1,198 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::constant::Constant;
+use rhdl_fpga::core::dff::DFF;
+use crate::types::*;
+use crate::alu::fixed_mul;
+
+// etaj optional de proiectie: punctul corectat -> (rand, coloana, distanta) in range image
+//
+// unghiurile sunt in format "binary angle": o tura completa = 2^32, deci s32 se intoarce singur la +-180 grade
+// si azimutul interpretat fara semn merge direct in [0, 360) grade
+// atan2 + modulul se calculeaza cu CORDIC in mod vectoring, de doua ori:
+//   (x, y)    -> azimut + distanta orizontala
+//   (r_xy, z) -> elevatie + distanta 3D
+// coloana = azimut * columns / 2^32, randul = fasciculul cu elevatia cea mai apropiata din tabel
+//
+// pixelul e doar in LidarOutput; pachetul de pe magistrala de 32 biti (bus.rs) ramane de 4 cuvinte,
+// altfel nu s-ar mai termina in perioada de 4 cicluri dintre doua puncte
+
+// numarul de fascicule verticale (VLP-16), dimensiunea tabelului din configurare
+pub const RANGE_BEAMS: usize = 16;
+
+// rezultatul unei rotatii vectoring: modulul si unghiul vectorului
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct Polar {
+    pub magnitude: Fixed32,
+    pub angle: s32,
+}
+
+// configurarea imaginii, fixata la sinteza
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct RangeImageConfig {
+    pub enable: bool,
+    // rezolutia orizontala (numarul de coloane, maxim 4095)
+    pub columns: b12,
+    // elevatia fiecarui fascicul in binary angle, descrescator (randul 0 = fasciculul de sus)
+    pub beams: [s32; RANGE_BEAMS],
+}
+
+// grade -> binary angle (o tura = 2^32)
+pub fn deg_to_angle(deg: f64) -> s32 {
+    let turns = deg / 360.0;
+    s32::from((turns * 4294967296.0).round() as i128)
+}
+
+impl RangeImageConfig {
+    // etajul ocolit, iesirea are aceeasi latenta ca inainte
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    // senzor cu fascicule egal distantate intre 'top_deg' si 'bottom_deg'
+    pub fn uniform(columns: u16, top_deg: f64, bottom_deg: f64) -> Self {
+        let step = (top_deg - bottom_deg) / (RANGE_BEAMS - 1) as f64;
+        let mut beams = [s32::default(); RANGE_BEAMS];
+        for (i, beam) in beams.iter_mut().enumerate() {
+            *beam = deg_to_angle(top_deg - step * i as f64);
+        }
+        Self {
+            enable: true,
+            columns: b12::from(columns as u128),
+            beams,
+        }
+    }
+
+    // Velodyne VLP-16: +15 .. -15 grade, pas de 2 grade
+    pub fn vlp16(columns: u16) -> Self {
+        Self::uniform(columns, 15.0, -15.0)
+    }
+}
+
+// CORDIC vectoring: roteste (x, y) pana pe axa x, acumuland unghiul
+// intoarce modulul (corectat cu 1/K) si atan2(y, x)
+#[kernel]
+pub fn cordic_vectoring(x: Fixed32, y: Fixed32) -> Polar {
+    // atan(2^-i) in binary angle
+    let atan_table: [s32; 16] = [
+        signed(536870912), signed(316933406), signed(167458907), signed(85004756),
+        signed(42667331), signed(21354465), signed(10679838), signed(5340245),
+        signed(2670163), signed(1335087), signed(667544), signed(333772),
+        signed(166886), signed(83443), signed(41722), signed(20861),
+    ];
+
+    let mut cx = x;
+    let mut cy = y;
+    let mut angle: s32 = signed(0);
+
+    // CORDIC converge doar in semiplanul drept, asa ca rotim intai cu +-90 grade
+    if x < signed(0) {
+        if y >= signed(0) {
+            cx = y;
+            cy = -x;
+            angle = signed(0x40000000);
+        } else {
+            cx = -y;
+            cy = x;
+            angle = signed(-0x40000000);
+        }
+    }
+
+    // 16 iteratii, eroarea de unghi ramane sub atan(2^-15) ~ 0.002 grade
+    for i in 0..16 {
+        let dx = cy >> i;
+        let dy = cx >> i;
+        if cy >= signed(0) {
+            cx = cx + dx;
+            cy = cy - dy;
+            angle = angle + atan_table[i];
+        } else {
+            cx = cx - dx;
+            cy = cy + dy;
+            angle = angle - atan_table[i];
+        }
+    }
+
+    // 1 / K (castigul CORDIC dupa 16 iteratii) = 0.607253 in 16.16
+    Polar {
+        magnitude: fixed_mul(cx, signed(39797)),
+        angle,
+    }
+}
+
+// proiectia unui punct in range image
+#[kernel]
+pub fn project_point(cfg: RangeImageConfig, p: Vector3) -> RangePixel {
+    let horizontal = cordic_vectoring(p.x, p.y);
+    let vertical = cordic_vectoring(horizontal.magnitude, p.z);
+
+    // coloana: azimutul fara semn inmultit cu numarul de coloane, pastram partea intreaga
+    let azimuth: b44 = horizontal.angle.as_unsigned().resize();
+    let columns: b44 = cfg.columns.resize();
+    let column: b12 = ((azimuth * columns) >> 32).resize();
+
+    // randul: cate praguri sunt deasupra punctului
+    // pragul i e la jumatatea distantei dintre fasciculele i si i+1
+    let mut row: b6 = bits(0);
+    for i in 0..15 {
+        let threshold = (cfg.beams[i] >> 1) + (cfg.beams[i + 1] >> 1);
+        if vertical.angle < threshold {
+            row = row + bits(1);
+        }
+    }
+
+    RangePixel {
+        valid: true,
+        row,
+        column,
+        range: vertical.magnitude,
+    }
+}
+
+// etajul de proiectie, pus dupa engine
+// activ: adauga un ciclu de latenta (pixelul e calculat combinational si registrat odata cu punctul)
+// inactiv: iesirea engine-ului trece direct, sinteza elimina registrul si CORDIC-ul
+#[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+pub struct RangeProjector {
+    cfg: Constant<RangeImageConfig>,
+    out: DFF<LidarOutput>,
+}
+
+impl RangeProjector {
+    pub fn new(cfg: RangeImageConfig) -> Self {
+        Self {
+            cfg: Constant::new(cfg),
+            out: DFF::new(LidarOutput::default()),
+        }
+    }
+}
+
+impl Default for RangeProjector {
+    fn default() -> Self {
+        Self::new(RangeImageConfig::disabled())
+    }
+}
+
+// interfata io
+impl SynchronousIO for RangeProjector {
+    type I = LidarOutput;
+    type O = LidarOutput;
+    type Kernel = range_projector_kernel;
+}
+
+#[kernel]
+pub fn range_projector_kernel(_cr: ClockReset, input: LidarOutput, q: Q) -> (LidarOutput, D) {
+    let cfg = q.cfg;
+
+    let mut projected = input;
+    projected.pixel = project_point(cfg, input.corrected_point);
+
+    let mut output = input;
+    if cfg.enable {
+        output = q.out;
+    }
+
+    (output, D {
+        cfg: (),
+        out: projected,
+    })
+}
@@ -0,0 +1,66 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::dff::DFF;
+
+// urmarirea scanarilor: numara punctele emise intre 'first' si 'last'
+// cand ultimul punct al scanarii iese din pipeline, scan_done e activ un ciclu (odata cu punctul)
+// iar scan_count contine numarul de puncte din scanare; valoarea ramane tinuta pana la urmatoarea scanare
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct ScanEvents {
+    pub valid: bool,
+    pub first: bool,
+    pub last: bool,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct ScanStatus {
+    pub scan_done: bool,
+    pub scan_count: b32,
+}
+
+#[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+pub struct ScanTracker {
+    // puncte emise in scanarea curenta
+    count: DFF<b32>,
+    // numarul de puncte al ultimei scanari terminate
+    last_count: DFF<b32>,
+}
+
+impl Default for ScanTracker {
+    fn default() -> Self {
+        Self {
+            count: DFF::new(b32::default()),
+            last_count: DFF::new(b32::default()),
+        }
+    }
+}
+
+impl SynchronousIO for ScanTracker {
+    type I = ScanEvents;
+    type O = ScanStatus;
+    type Kernel = scan_kernel;
+}
+
+#[kernel]
+pub fn scan_kernel(_cr: ClockReset, ev: ScanEvents, q: Q) -> (ScanStatus, D) {
+    let mut d = D { count: q.count, last_count: q.last_count };
+    let mut status = ScanStatus { scan_done: false, scan_count: q.last_count };
+
+    if ev.valid {
+        // primul punct reporneste numaratoarea (chiar daca scanarea anterioara nu a avut 'last')
+        let mut count = q.count + bits(1);
+        if ev.first {
+            count = bits(1);
+        }
+        d.count = count;
+
+        if ev.last {
+            status.scan_done = true;
+            status.scan_count = count;
+            d.last_count = count;
+            d.count = bits(0);
+        }
+    }
+
+    (status, d)
+}
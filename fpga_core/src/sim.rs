@@ -0,0 +1,24 @@
+use rhdl::prelude::*;
+
+// utilitare pentru testbench-uri si pentru simularea din host
+
+// din esantioanele simularii pastram cate unul pe ciclu de ceas (primul dupa frontul pozitiv)
+// intoarce perechi (intrare, iesire) in ordinea ciclurilor
+pub fn cycle_samples<I: Digital, O: Digital>(
+    samples: impl IntoIterator<Item = TimedSample<(ClockReset, I, O)>>,
+) -> Vec<(I, O)> {
+    let mut cycles = Vec::new();
+    let mut prev_clock = false;
+
+    for sample in samples {
+        let (cr, input, output) = sample.value;
+        let clock = cr.clock.raw();
+
+        if clock && !prev_clock {
+            cycles.push((input, output));
+        }
+        prev_clock = clock;
+    }
+
+    cycles
+}
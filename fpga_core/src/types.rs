@@ -6,6 +6,14 @@ pub type Fixed32 = s32;
 // pt impartire
 pub const FRAC_BITS: u32 = 16;
 
+// elementele matricei de rotatie sunt mereu in [-1, 1], deci nu au nevoie de 16 biti intregi
+// format Q2.16: semn + 1 bit intreg (ca sa incapa exact +1.0) + 16 biti fractionari, pe portul de 18 biti al DSP48
+pub type RotFixed = s18;
+
+// coordonata de punct care intra in inmultirea cu rotatia: 16.16 pe 25 de biti (portul lung al DSP48), deci +-256 m
+// impreuna cu RotFixed, fiecare produs R * P ocupa exact un DSP48 (vezi alu::point_slice)
+pub type PointFixed = s25;
+
 // structurile de date
 #[derive(PartialEq, Debug, Digital, Default)]
 pub struct Vector3 {
@@ -16,8 +24,32 @@ pub struct Vector3 {
 
 #[derive(PartialEq, Debug, Digital, Default)]
 pub struct Matrix3x3 {
-    // array suportat nativ
-    pub rows: [[Fixed32; 3]; 3],
+    // array suportat nativ, in format Q2.16 (vezi RotFixed)
+    pub rows: [[RotFixed; 3]; 3],
+}
+
+// transformare afina [R | t], echivalentul matricei omogene 4x4 fara ultima linie (mereu 0 0 0 1)
+// un punct p devine R * p + t, iar lanturile de transformari se compun cu affine_compose
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct Matrix4x4 {
+    pub rotation: Matrix3x3,
+    pub translation: Vector3,
+}
+
+// numarul de contexte de pose din Engine (mai multi senzori sau scanari suprapuse)
+// indexul unui context e pe 2 biti (ctx_id / pose_ctx)
+pub const POSE_CONTEXTS: usize = 4;
+
+// pose-urile tuturor contextelor, indexate cu ctx_id
+pub type PoseContexts = [Matrix4x4; POSE_CONTEXTS];
+
+// ce valoare intoarce LidarOutput.corrected_point, pentru depanare fara VCD
+#[derive(PartialEq, Debug, Digital, Default)]
+pub enum DebugSel {
+    #[default]
+    Final,   // R * P + T (functionarea normala)
+    Raw,     // punctul de intrare, nemodificat
+    Rotated, // doar R * P (registrul temp_rotated)
 }
 
 // i/o interface
@@ -28,6 +60,80 @@ pub struct LidarInput {
     pub point: Vector3,
     pub rotation: Matrix3x3,
     pub translation: Vector3,
+    // scrie rotation/translation in registrele shadow (se poate face oricand, nu opreste calculul)
+    pub pose_write: bool,
+    // cere inlocuirea pose-ului activ cu cel din shadow, aplicata la urmatorul punct incarcat
+    pub pose_swap: bool,
+    // porneste self-test-ul intern (cat timp e activ, restul intrarii e ignorat)
+    pub bist_enable: bool,
+    // marcheaza primul / ultimul punct dintr-o scanare
+    pub first: bool,
+    pub last: bool,
+    // impreuna cu pose_write: shadow = shadow * [rotation | translation] in loc de suprascriere
+    // asa host-ul poate trimite pe rand extrinsic, miscare si inversa referintei
+    pub pose_compose: bool,
+    // selectia de depanare, tinuta odata cu punctul
+    pub debug_sel: DebugSel,
+    // modul cu viteze constante pe scanare: pose-ul punctului se calculeaza in hardware
+    // R = I + [omega * dt]x (aproximare de ordinul 1, unghiuri mici), T = velocity * dt
+    // rotation/translation si registrele shadow sunt ignorate pentru punctele din acest mod
+    pub velocity_mode: bool,
+    pub omega: Vector3,    // viteza unghiulara (rad/s, 16.16)
+    pub velocity: Vector3, // viteza liniara (m/s, 16.16)
+    pub dt: Fixed32,       // timpul punctului fata de inceputul scanarii (s, 16.16)
+    // contextul de pose aplicat punctului (tinut odata cu punctul, intors in LidarOutput)
+    pub ctx_id: b2,
+    // contextul in care scriu pose_write / pose_compose si pe care il activeaza pose_swap
+    pub pose_ctx: b2,
+}
+
+// contoarele de performanta, citite de host prin iesirea de status
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct PerfStatus {
+    pub cycles: b32,      // cicluri de la reset
+    pub accepted: b32,    // puncte acceptate
+    pub emitted: b32,     // puncte corectate emise
+    pub dropped: b32,     // puncte venite cat timp automatul era ocupat (pierdute)
+    pub busy_cycles: b32, // cicluri in care automatul nu era in Idle
+    pub max_latency: b16, // latenta maxima observata (cicluri de la acceptare la iesire)
+}
+
+// rezultatul self-test-ului (BIST)
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct BistStatus {
+    pub running: bool,
+    pub done: bool,
+    pub pass: bool,
+    pub fail: bool,
+    pub fail_index: b4, // primul vector care a dat rezultat gresit
+}
+
+// pixelul din imaginea de distante (range image) corespunzator punctului corectat
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct RangePixel {
+    pub valid: bool,    // etajul de proiectie e activ si a calculat pixelul
+    pub row: b6,        // fasciculul vertical (0 = cel mai de sus)
+    pub column: b12,    // coloana, din azimut
+    pub range: Fixed32, // distanta 3D pana la punct (16.16)
+}
+
+// unitatea coordonatelor intregi din etajul de formatare (format.rs)
+#[derive(PartialEq, Debug, Digital, Default)]
+pub enum CompactUnit {
+    #[default]
+    Millimetre,
+    Centimetre,
+}
+
+// punctul corectat in unitati intregi, impachetat
+// wide: x, y, z pe cate 32 biti (x in bitii 0..=31, y in 32..=63, z in 64..=95)
+// altfel pe cate 16 biti (x in 0..=15, y in 16..=31, z in 32..=47), saturate la [-32768, 32767]
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct CompactPoint {
+    pub valid: bool, // etajul de formatare e activ si punctul e valid
+    pub wide: bool,
+    pub unit: CompactUnit,
+    pub data: b96,
 }
 
 #[derive(PartialEq, Debug, Digital, Default)]
@@ -35,4 +141,21 @@ pub struct LidarOutput {
     // sunt date valide pentru output
     pub valid: bool,
     pub corrected_point: Vector3,
+    // proiectia punctului in range image (completata de etajul optional din projection.rs)
+    pub pixel: RangePixel,
+    // coordonatele in unitati intregi (completate de etajul optional din format.rs)
+    pub compact: CompactPoint,
+    // contextul de pose cu care a fost corectat punctul (ctx_id de la intrare)
+    pub ctx_id: b2,
+    // marcajele de scanare ale punctului, propagate prin pipeline
+    pub first: bool,
+    pub last: bool,
+    // puls de un ciclu odata cu ultimul punct al scanarii + numarul de puncte din scanare
+    pub scan_done: bool,
+    pub scan_count: b32,
+    // automatul e in Idle si BIST-ul nu ruleaza: un punct valid trimis in acest ciclu e acceptat
+    pub ready: bool,
+    // status-ul contoarelor de performanta (valid in orice ciclu)
+    pub status: PerfStatus,
+    pub bist: BistStatus,
 }
\ No newline at end of file
@@ -0,0 +1,480 @@
+use rhdl::prelude::*;
+use rhdl_fpga::core::dff::DFF;
+use crate::types::*;
+use crate::bus::{assemble_word, hold_data, output_word};
+use crate::LidarProcessor;
+
+// legatura seriala pentru bring-up in laborator: UART 8N1 + un protocol cu cadre
+//
+// cadrul de cerere (PC -> FPGA):  0xA5, LEN = 64, 16 cuvinte LidarInput (little-endian), CRC
+// cadrul de raspuns (FPGA -> PC): 0xA5, LEN = 16, 4 cuvinte LidarOutput (little-endian), CRC
+// ordinea cuvintelor e cea din bus.rs; CRC-8 (polinom 0x07, init 0) se calculeaza peste LEN + payload
+// cadrele cu LEN gresit sau CRC gresit sunt ignorate, host-ul retrimite dupa timeout
+//
+// viteza se da in cicluri de ceas per bit (ex: 100 MHz / 115200 = 868), ca in simulare sa putem folosi valori mici
+
+pub const FRAME_SYNC: u8 = 0xA5;
+pub const REQUEST_LEN: u8 = 64;
+pub const RESPONSE_LEN: u8 = 16;
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct UartByte {
+    pub valid: bool,
+    pub data: b8,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct UartRxIn {
+    pub line: bool,
+    pub clks_per_bit: b16,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct UartTxIn {
+    pub start: bool,
+    pub data: b8,
+    pub clks_per_bit: b16,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct UartTxOut {
+    pub line: bool,
+    pub busy: bool,
+}
+
+// CRC-8, polinom 0x07, un octet
+#[kernel]
+pub fn crc8_update(crc: b8, byte: b8) -> b8 {
+    let mut c = crc ^ byte;
+    for _i in 0..8 {
+        if (c & bits(0x80)) != bits(0) {
+            c = (c << 1) ^ bits(0x07);
+        } else {
+            c = c << 1;
+        }
+    }
+    c
+}
+
+// fiecare componenta sta in submodulul ei (SynchronousDQ genereaza Q si D la nivelul modulului),
+// re-exportat ca sa pastram caile uart::UartRx etc.
+
+// receptor UART 8N1
+pub mod rx {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct UartRx {
+        // doua registre de sincronizare pe linia asincrona
+        sync1: DFF<bool>,
+        sync2: DFF<bool>,
+        busy: DFF<bool>,
+        count: DFF<b16>,
+        // 0 = bitul de start, 1..=8 = date, 9 = stop
+        bit_index: DFF<b4>,
+        shift: DFF<b8>,
+    }
+
+    impl Default for UartRx {
+        fn default() -> Self {
+            Self {
+                sync1: DFF::new(true),
+                sync2: DFF::new(true),
+                busy: DFF::new(false),
+                count: DFF::new(b16::default()),
+                bit_index: DFF::new(b4::default()),
+                shift: DFF::new(b8::default()),
+            }
+        }
+    }
+
+    impl SynchronousIO for UartRx {
+        type I = UartRxIn;
+        type O = UartByte;
+        type Kernel = uart_rx_kernel;
+    }
+
+    #[kernel]
+    pub fn uart_rx_kernel(_cr: ClockReset, input: UartRxIn, q: Q) -> (UartByte, D) {
+        let rx = q.sync2;
+        let mut output = UartByte::default();
+        let mut d = D {
+            sync1: input.line,
+            sync2: q.sync1,
+            busy: q.busy,
+            count: q.count,
+            bit_index: q.bit_index,
+            shift: q.shift,
+        };
+
+        if !q.busy {
+            // frontul descrescator al bitului de start, esantionam la jumatatea bitului
+            if !rx {
+                d.busy = true;
+                d.count = input.clks_per_bit >> 1;
+                d.bit_index = bits(0);
+            }
+        } else if q.count != bits(0) {
+            d.count = q.count - bits(1);
+        } else {
+            d.count = input.clks_per_bit - bits(1);
+            if q.bit_index == bits(0) {
+                // start-ul trebuie sa fie inca 0, altfel a fost un glitch
+                if rx {
+                    d.busy = false;
+                }
+                d.bit_index = bits(1);
+            } else if q.bit_index == bits(9) {
+                // octetul e valid doar daca bitul de stop e 1
+                output.valid = rx;
+                output.data = q.shift;
+                d.busy = false;
+            } else {
+                // LSB first
+                let mut msb: b8 = bits(0);
+                if rx {
+                    msb = bits(0x80);
+                }
+                d.shift = msb | (q.shift >> 1);
+                d.bit_index = q.bit_index + bits(1);
+            }
+        }
+
+        (output, d)
+    }
+}
+pub use rx::{UartRx, uart_rx_kernel};
+
+// transmitator UART 8N1
+pub mod tx {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct UartTx {
+        busy: DFF<bool>,
+        count: DFF<b16>,
+        bit_index: DFF<b4>,
+        // start + 8 date + stop, trimis de la bitul 0
+        shift: DFF<b10>,
+    }
+
+    impl Default for UartTx {
+        fn default() -> Self {
+            Self {
+                busy: DFF::new(false),
+                count: DFF::new(b16::default()),
+                bit_index: DFF::new(b4::default()),
+                shift: DFF::new(b10::default()),
+            }
+        }
+    }
+
+    impl SynchronousIO for UartTx {
+        type I = UartTxIn;
+        type O = UartTxOut;
+        type Kernel = uart_tx_kernel;
+    }
+
+    #[kernel]
+    pub fn uart_tx_kernel(_cr: ClockReset, input: UartTxIn, q: Q) -> (UartTxOut, D) {
+        let mut d = D {
+            busy: q.busy,
+            count: q.count,
+            bit_index: q.bit_index,
+            shift: q.shift,
+        };
+
+        if !q.busy {
+            if input.start {
+                let data: b10 = input.data.resize();
+                d.shift = bits(0x200) | (data << 1);
+                d.busy = true;
+                d.count = input.clks_per_bit - bits(1);
+                d.bit_index = bits(0);
+            }
+        } else if q.count != bits(0) {
+            d.count = q.count - bits(1);
+        } else if q.bit_index == bits(9) {
+            d.busy = false;
+        } else {
+            d.shift = q.shift >> 1;
+            d.bit_index = q.bit_index + bits(1);
+            d.count = input.clks_per_bit - bits(1);
+        }
+
+        // linia e 1 in repaus
+        let mut line = true;
+        if q.busy {
+            line = (q.shift & bits(1)) != bits(0);
+        }
+
+        (UartTxOut { line, busy: q.busy }, d)
+    }
+}
+pub use tx::{UartTx, uart_tx_kernel};
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub enum FrameRxState {
+    #[default]
+    Sync,
+    Len,
+    Payload,
+    Crc,
+}
+
+// octeti -> LidarInput, doar pentru cadrele cu CRC corect
+pub mod frame_rx {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct FrameRx {
+        state: DFF<FrameRxState>,
+        byte_index: DFF<b6>,
+        word: DFF<b32>,
+        acc: DFF<LidarInput>,
+        crc: DFF<b8>,
+    }
+
+    impl Default for FrameRx {
+        fn default() -> Self {
+            Self {
+                state: DFF::new(FrameRxState::Sync),
+                byte_index: DFF::new(b6::default()),
+                word: DFF::new(b32::default()),
+                acc: DFF::new(LidarInput::default()),
+                crc: DFF::new(b8::default()),
+            }
+        }
+    }
+
+    impl SynchronousIO for FrameRx {
+        type I = UartByte;
+        type O = LidarInput;
+        type Kernel = frame_rx_kernel;
+    }
+
+    #[kernel]
+    pub fn frame_rx_kernel(_cr: ClockReset, byte: UartByte, q: Q) -> (LidarInput, D) {
+        // datele raman tinute pentru automat, flag-urile sunt un singur ciclu (ca la deserializator)
+        let mut output = hold_data(q.acc);
+        let mut d = D {
+            state: q.state,
+            byte_index: q.byte_index,
+            word: q.word,
+            acc: q.acc,
+            crc: q.crc,
+        };
+
+        if byte.valid {
+            match q.state {
+                FrameRxState::Sync => {
+                    if byte.data == bits(0xA5) {
+                        d.state = FrameRxState::Len;
+                    }
+                },
+                FrameRxState::Len => {
+                    if byte.data == bits(64) {
+                        d.state = FrameRxState::Payload;
+                        d.byte_index = bits(0);
+                        d.crc = crc8_update(bits(0), byte.data);
+                    } else {
+                        d.state = FrameRxState::Sync;
+                    }
+                },
+                FrameRxState::Payload => {
+                    // little-endian: primul octet e cel mai putin semnificativ
+                    let byte_wide: b32 = byte.data.resize();
+                    let word = (byte_wide << 24) | (q.word >> 8);
+                    d.word = word;
+                    d.crc = crc8_update(q.crc, byte.data);
+
+                    if (q.byte_index & bits(3)) == bits(3) {
+                        let index: b5 = (q.byte_index >> 2).resize();
+                        d.acc = assemble_word(q.acc, index, word);
+                    }
+                    if q.byte_index == bits(63) {
+                        d.state = FrameRxState::Crc;
+                    } else {
+                        d.byte_index = q.byte_index + bits(1);
+                    }
+                },
+                FrameRxState::Crc => {
+                    if byte.data == q.crc {
+                        output = q.acc;
+                    }
+                    d.acc = hold_data(q.acc);
+                    d.state = FrameRxState::Sync;
+                },
+            }
+        }
+
+        (output, d)
+    }
+}
+pub use frame_rx::{FrameRx, frame_rx_kernel};
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct FrameTxIn {
+    pub output: LidarOutput,
+    pub tx_busy: bool,
+}
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct FrameTxOut {
+    pub start: bool,
+    pub data: b8,
+}
+
+// LidarOutput -> cadru de raspuns, cate un octet catre UartTx
+// host-ul asteapta raspunsul inainte sa trimita urmatorul punct, deci nu avem nevoie de coada
+pub mod frame_tx {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct FrameTx {
+        active: DFF<bool>,
+        // 0 = sync, 1 = len, 2..=17 = payload, 18 = crc
+        byte_index: DFF<b5>,
+        data: DFF<LidarOutput>,
+        crc: DFF<b8>,
+        // am dat start in ciclul trecut, UartTx nu a ridicat inca busy
+        just_sent: DFF<bool>,
+    }
+
+    impl Default for FrameTx {
+        fn default() -> Self {
+            Self {
+                active: DFF::new(false),
+                byte_index: DFF::new(b5::default()),
+                data: DFF::new(LidarOutput::default()),
+                crc: DFF::new(b8::default()),
+                just_sent: DFF::new(false),
+            }
+        }
+    }
+
+    impl SynchronousIO for FrameTx {
+        type I = FrameTxIn;
+        type O = FrameTxOut;
+        type Kernel = frame_tx_kernel;
+    }
+
+    // octetul 'index' din payload-ul de raspuns
+    #[kernel]
+    pub fn response_byte(output: LidarOutput, index: b5) -> b8 {
+        let word_index: b3 = (index >> 2).resize();
+        let word = output_word(output, word_index);
+        let lane = index & bits(3);
+
+        let mut byte: b8 = word.resize();
+        if lane == bits(1) {
+            byte = (word >> 8).resize();
+        } else if lane == bits(2) {
+            byte = (word >> 16).resize();
+        } else if lane == bits(3) {
+            byte = (word >> 24).resize();
+        }
+        byte
+    }
+
+    #[kernel]
+    pub fn frame_tx_kernel(_cr: ClockReset, input: FrameTxIn, q: Q) -> (FrameTxOut, D) {
+        let mut d = D {
+            active: q.active,
+            byte_index: q.byte_index,
+            data: q.data,
+            crc: q.crc,
+            just_sent: false,
+        };
+
+        let mut byte: b8 = bits(0xA5);
+        if q.byte_index == bits(1) {
+            byte = bits(16);
+        } else if q.byte_index == bits(18) {
+            byte = q.crc;
+        } else if q.byte_index != bits(0) {
+            byte = response_byte(q.data, q.byte_index - bits(2));
+        }
+
+        let mut output = FrameTxOut { start: false, data: byte };
+
+        if !q.active {
+            if input.output.valid {
+                d.active = true;
+                d.data = input.output;
+                d.byte_index = bits(0);
+                d.crc = bits(0);
+            }
+        } else if !input.tx_busy && !q.just_sent {
+            output.start = true;
+            d.just_sent = true;
+            if q.byte_index != bits(0) && q.byte_index != bits(18) {
+                d.crc = crc8_update(q.crc, byte);
+            }
+            if q.byte_index == bits(18) {
+                d.active = false;
+            } else {
+                d.byte_index = q.byte_index + bits(1);
+            }
+        }
+
+        (output, d)
+    }
+}
+pub use frame_tx::{FrameTx, response_byte, frame_tx_kernel};
+
+#[derive(PartialEq, Debug, Digital, Default)]
+pub struct UartLinkIn {
+    pub rx_line: bool,
+    pub clks_per_bit: b16,
+}
+
+// procesorul complet in spatele unei legaturi seriale
+pub mod top {
+    use super::*;
+
+    #[derive(Synchronous, SynchronousDQ, Clone, Debug)]
+    pub struct UartLidarCore {
+        rx: UartRx,
+        frame_rx: FrameRx,
+        core: LidarProcessor,
+        frame_tx: FrameTx,
+        tx: UartTx,
+    }
+
+    impl Default for UartLidarCore {
+        fn default() -> Self {
+            Self {
+                rx: UartRx::default(),
+                frame_rx: FrameRx::default(),
+                core: LidarProcessor::default(),
+                frame_tx: FrameTx::default(),
+                tx: UartTx::default(),
+            }
+        }
+    }
+
+    impl SynchronousIO for UartLidarCore {
+        type I = UartLinkIn;
+        type O = bool; // linia tx
+        type Kernel = uart_top_kernel;
+    }
+
+    #[kernel]
+    pub fn uart_top_kernel(_cr: ClockReset, input: UartLinkIn, q: Q) -> (bool, D) {
+        let mut d = D::dont_care();
+
+        d.rx = UartRxIn { line: input.rx_line, clks_per_bit: input.clks_per_bit };
+        d.frame_rx = q.rx;
+        d.core = q.frame_rx;
+        d.frame_tx = FrameTxIn { output: q.core, tx_busy: q.tx.busy };
+        d.tx = UartTxIn {
+            start: q.frame_tx.start,
+            data: q.frame_tx.data,
+            clks_per_bit: input.clks_per_bit,
+        };
+
+        (q.tx.line, d)
+    }
+}
+pub use top::{UartLidarCore, uart_top_kernel};
@@ -0,0 +1,200 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// precizia caii hardware (fixed point / simulare) fata de corectia de referinta in f64
+// eroarea unui punct este (hardware - referinta), pe fiecare axa, plus distanta 3D dintre ele
+// histograma e pe distanta 3D, cu bin-uri egale si un ultim bin pentru tot ce e peste
+// se compara iesirea 16.16 (corrected_point), nu formatul compact, ca rotunjirea lui sa nu intre in eroare;
+// punctele care au venit doar in format compact (magistrala in modul compact) sunt numarate separat
+
+const AXES: [&str; 3] = ["x", "y", "z"];
+
+// media, RMS-ul si maximul unei erori, adunate punct cu punct
+#[derive(Debug, Default, Clone, Copy)]
+struct ErrorStats {
+    sum: f64,
+    sum_sq: f64,
+    max_abs: f64,
+}
+
+impl ErrorStats {
+    fn add(&mut self, err: f64) {
+        self.sum += err;
+        self.sum_sq += err * err;
+        self.max_abs = self.max_abs.max(err.abs());
+    }
+
+    fn mean(&self, count: u64) -> f64 {
+        if count > 0 { self.sum / count as f64 } else { 0.0 }
+    }
+
+    fn rms(&self, count: u64) -> f64 {
+        if count > 0 { (self.sum_sq / count as f64).sqrt() } else { 0.0 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccuracyReport {
+    count: u64,
+    // puncte masurate pe formatul compact, cu rotunjirea inclusa
+    compact_count: u64,
+    axes: [ErrorStats; 3],
+    distance: ErrorStats,
+    bin_width_m: f64,
+    // ultimul bin numara tot ce e peste (bins - 1) * bin_width_m
+    histogram: Vec<u64>,
+}
+
+impl AccuracyReport {
+    pub fn new(bin_width_m: f64, bins: usize) -> Self {
+        assert!(bin_width_m > 0.0, "latimea unui bin trebuie sa fie pozitiva");
+        Self {
+            count: 0,
+            compact_count: 0,
+            axes: [ErrorStats::default(); 3],
+            distance: ErrorStats::default(),
+            bin_width_m,
+            histogram: vec![0; bins.max(1)],
+        }
+    }
+
+    pub fn add(&mut self, corrected: [f64; 3], reference: [f64; 3], compact: bool) {
+        let mut dist_sq = 0.0;
+        for (axis, (c, r)) in self.axes.iter_mut().zip(corrected.iter().zip(&reference)) {
+            let err = c - r;
+            axis.add(err);
+            dist_sq += err * err;
+        }
+        let dist = dist_sq.sqrt();
+        self.distance.add(dist);
+
+        let last = self.histogram.len() - 1;
+        let bin = ((dist / self.bin_width_m) as usize).min(last);
+        self.histogram[bin] += 1;
+        self.count += 1;
+        if compact {
+            self.compact_count += 1;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn print(&self) {
+        println!("Precizie fata de referinta f64 ({} puncte, iesirea 16.16 corrected_point):", self.count);
+        if self.compact_count > 0 {
+            println!("  {} puncte au venit doar in format compact, eroarea lor include rotunjirea", self.compact_count);
+        }
+        for (name, axis) in AXES.iter().zip(&self.axes) {
+            println!("  {}: medie {:+.6} m, RMS {:.6} m, max {:.6} m",
+                     name, axis.mean(self.count), axis.rms(self.count), axis.max_abs);
+        }
+        println!("  3D: medie {:.6} m, RMS {:.6} m, max {:.6} m",
+                 self.distance.mean(self.count), self.distance.rms(self.count), self.distance.max_abs);
+        let over = self.histogram.last().copied().unwrap_or(0);
+        if over > 0 {
+            println!("  {} puncte peste {:.6} m", over, self.bin_start(self.histogram.len() - 1));
+        }
+    }
+
+    fn bin_start(&self, bin: usize) -> f64 {
+        bin as f64 * self.bin_width_m
+    }
+
+    // raportul complet: JSON pentru '.json', altfel CSV
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        let mut out = BufWriter::new(File::create(path)?);
+        let json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if json {
+            self.write_json(&mut out)?;
+        } else {
+            self.write_csv(&mut out)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    fn write_json(&self, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        let stats_json = |s: &ErrorStats| format!(
+            "{{\"mean_m\": {}, \"rms_m\": {}, \"max_m\": {}}}",
+            json_number(s.mean(self.count)), json_number(s.rms(self.count)), json_number(s.max_abs)
+        );
+
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"compared_output\": \"corrected_point_16_16\",")?;
+        writeln!(out, "  \"points\": {},", self.count)?;
+        writeln!(out, "  \"compact_points\": {},", self.compact_count)?;
+        for (name, axis) in AXES.iter().zip(&self.axes) {
+            writeln!(out, "  \"{}\": {},", name, stats_json(axis))?;
+        }
+        writeln!(out, "  \"distance\": {},", stats_json(&self.distance))?;
+        writeln!(out, "  \"histogram\": {{")?;
+        writeln!(out, "    \"bin_width_m\": {},", json_number(self.bin_width_m))?;
+        let counts: Vec<String> = self.histogram.iter().map(|c| c.to_string()).collect();
+        writeln!(out, "    \"counts\": [{}]", counts.join(", "))?;
+        writeln!(out, "  }}")?;
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+
+    // doua tabele: statisticile pe axe, apoi histograma (from_m, to_m, count; ultimul bin fara capat)
+    fn write_csv(&self, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        writeln!(out, "# compared_output corrected_point_16_16, compact_points {}", self.compact_count)?;
+        writeln!(out, "axis,points,mean_m,rms_m,max_m")?;
+        for (name, axis) in AXES.iter().zip(&self.axes).chain(std::iter::once((&"distance", &self.distance))) {
+            writeln!(out, "{},{},{:e},{:e},{:e}", name, self.count, axis.mean(self.count), axis.rms(self.count), axis.max_abs)?;
+        }
+        writeln!(out)?;
+        writeln!(out, "from_m,to_m,count")?;
+        let last = self.histogram.len() - 1;
+        for (bin, count) in self.histogram.iter().enumerate() {
+            let to = if bin == last { String::new() } else { format!("{:e}", self.bin_start(bin + 1)) };
+            writeln!(out, "{:e},{},{}", self.bin_start(bin), to, count)?;
+        }
+        Ok(())
+    }
+}
+
+// JSON nu are NaN / infinit: o eroare nefinita (ex: punct saturat) se scrie ca null
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:e}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(report: &AccuracyReport) -> String {
+        let mut out = Vec::new();
+        report.write_json(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn histogram_bins_and_overflow() {
+        let mut report = AccuracyReport::new(0.001, 3);
+        report.add([0.0005, 0.0, 0.0], [0.0; 3], false);
+        report.add([0.0, 0.0015, 0.0], [0.0; 3], false);
+        report.add([0.0, 0.0, 1.0], [0.0; 3], true);
+        assert_eq!(report.histogram, vec![1, 1, 1]);
+        assert_eq!(report.count(), 3);
+        assert_eq!(report.compact_count, 1);
+    }
+
+    #[test]
+    fn non_finite_errors_are_null_in_json() {
+        let mut report = AccuracyReport::new(0.001, 2);
+        report.add([f64::NAN, 0.0, 0.0], [0.0; 3], false);
+        let text = json(&report);
+        assert!(text.contains("\"mean_m\": null"), "{}", text);
+        assert!(!text.contains("NaN") && !text.contains("inf"), "{}", text);
+    }
+}
@@ -0,0 +1,298 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::rc::Rc;
+
+use rhdl::prelude::*;
+use fpga_core::{LidarProcessor, LidarInput, LidarOutput, RangePixel, RangeImageConfig, CompactPoint, OutputFormat};
+use fpga_core::types::Vector3 as FpgaVec3;
+use fpga_core::coverage::Coverage;
+
+use crate::perf_report::PerfTotals;
+
+// rezultatul unui punct: coordonatele corectate in 16.16 + semnalizarea de sfarsit de scanare
+// pixelul din range image e valid doar daca hardware-ul are etajul de proiectie activ
+#[derive(Debug, Clone, Copy)]
+pub struct CoreOutput {
+    // corrected_point, nerotunjit; None doar cand magistrala a adus formatul compact in locul lui
+    pub point: Option<FpgaVec3>,
+    pub scan_done: bool,
+    pub pixel: RangePixel,
+    // coordonatele intregi, valide doar cu etajul de formatare activ (vezi point_decoder.rs)
+    pub compact: CompactPoint,
+    // contextul de pose aplicat, ecoul lui ctx_id de la intrare (vezi check_context)
+    pub ctx_id: u8,
+}
+
+// hardware-ul intoarce contextul cu care a corectat punctul; daca difera de cel cerut,
+// punctul a fost corectat cu alt pose sau rezultatul nu apartine acestei intrari
+pub fn check_context(input: &LidarInput, output: &CoreOutput) -> Result<(), Box<dyn Error>> {
+    let expected = input.ctx_id.raw() as u8;
+    if output.ctx_id != expected {
+        return Err(format!("rezultat cu contextul {} pentru o intrare pe contextul {}", output.ctx_id, expected).into());
+    }
+    Ok(())
+}
+
+// un backend primeste intrarea completa (punct + pose) si intoarce punctul corectat
+// None inseamna ca hardware-ul nu a raspuns cu un rezultat valid
+pub trait Backend {
+    fn process(&mut self, input: &LidarInput) -> Result<Option<CoreOutput>, Box<dyn Error>>;
+
+    // o scanare intreaga, punctele in ordine; rezultatele corespund intrarilor, unul la unul
+    // implicit punct cu punct, backend-urile care pot trimite un flux continuu il suprascriu
+    fn process_batch(&mut self, inputs: &[LidarInput]) -> Result<Vec<Option<CoreOutput>>, Box<dyn Error>> {
+        inputs.iter().map(|input| self.process(input)).collect()
+    }
+
+    // contoarele hardware, daca backend-ul le poate citi
+    fn perf(&self) -> Option<&PerfTotals> {
+        None
+    }
+
+    // acoperirea automatului si a registrelor, daca backend-ul o colecteaza
+    fn coverage(&self) -> Option<&Coverage> {
+        None
+    }
+}
+
+// simularea cycle-accurate a LidarProcessor, in proces
+pub struct SimBackend {
+    perf: PerfTotals,
+    range_image: RangeImageConfig,
+    format: OutputFormat,
+    coverage: Option<Coverage>,
+}
+
+impl SimBackend {
+    pub fn new(range_image: RangeImageConfig) -> Self {
+        Self { perf: PerfTotals::default(), range_image, format: OutputFormat::disabled(), coverage: None }
+    }
+
+    // hardware-ul intoarce si coordonatele in unitati intregi
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // fiecare scanare e urmarita si in VCD (in memorie), deci rularea e mai lenta
+    pub fn with_coverage(mut self) -> Self {
+        self.coverage = Some(Coverage::new());
+        self
+    }
+}
+
+// cati cicluri fara progres (confirmare sau rezultat) asteptam pana declaram simularea blocata
+const STALL_CYCLES: usize = 64;
+// ciclii de la final, ca ultimul status al contoarelor sa includa si ultimul punct
+const TAIL_CYCLES: usize = 2;
+
+// alimentarea simularii prin handshake: un singur punct in zbor, trimis doar cand nucleul e ready,
+// iar urmatorul abia dupa ce contoarele l-au confirmat (acceptat sau pierdut) si nucleul e din nou ready
+// rezultatele se leaga de intrari prin coada celor acceptate, in ordine, nu dupa pozitie
+struct Feed<'a> {
+    inputs: &'a [LidarInput],
+    // urmatoarea intrare netrimisa
+    next: usize,
+    // intrarea trimisa, inca neconfirmata de contoare
+    in_flight: Option<usize>,
+    // ready observat dupa ultima confirmare
+    ready: bool,
+    // contoarele accepted / dropped din ultimul status observat
+    accepted: u128,
+    dropped: u128,
+    // intrarile acceptate care inca asteapta rezultatul
+    pending: VecDeque<usize>,
+    // cicluri de la ultimul progres, respectiv ciclii ramasi dupa ultimul rezultat
+    stalled: usize,
+    tail: usize,
+    // prima eroare gasita la iesire; opreste simularea
+    error: Option<Box<dyn Error>>,
+}
+
+impl<'a> Feed<'a> {
+    fn new(inputs: &'a [LidarInput]) -> Self {
+        Self {
+            inputs,
+            next: 0,
+            in_flight: None,
+            ready: false,
+            accepted: 0,
+            dropped: 0,
+            pending: VecDeque::new(),
+            stalled: 0,
+            tail: TAIL_CYCLES,
+            error: None,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.next == self.inputs.len() && self.in_flight.is_none() && self.pending.is_empty()
+    }
+
+    // intrarea pentru ciclul urmator; None opreste simularea
+    fn drive(&mut self) -> Option<LidarInput> {
+        if self.error.is_some() {
+            return None;
+        }
+        if self.finished() {
+            if self.tail == 0 {
+                return None;
+            }
+            self.tail -= 1;
+        } else if self.stalled > STALL_CYCLES {
+            return None;
+        }
+        self.stalled += 1;
+
+        if self.in_flight.is_none() && self.ready && self.next < self.inputs.len() {
+            // pose-ul se scrie in shadow si se activeaza odata cu punctul
+            let input = LidarInput {
+                valid: true,
+                pose_write: true,
+                pose_swap: true,
+                ..self.inputs[self.next]
+            };
+            self.in_flight = Some(self.next);
+            self.next += 1;
+            self.ready = false;
+            return Some(input);
+        }
+
+        // cat timp automatul lucreaza, ultima intrare e tinuta fara valid
+        Some(match self.next.checked_sub(1) {
+            Some(last) => LidarInput {
+                valid: false,
+                pose_write: false,
+                pose_swap: false,
+                ..self.inputs[last]
+            },
+            None => LidarInput::default(),
+        })
+    }
+
+    // iesirea unui ciclu: confirmarea punctului in zbor, ready si indexul intrarii careia ii apartine rezultatul
+    fn observe(&mut self, output: &LidarOutput) -> Result<Option<usize>, Box<dyn Error>> {
+        let accepted = output.status.accepted.raw();
+        let dropped = output.status.dropped.raw();
+        if accepted != self.accepted || dropped != self.dropped {
+            let index = self.in_flight.take().ok_or("contoarele s-au schimbat fara un punct trimis")?;
+            // un punct pierdut ramane fara rezultat, pe pozitia lui
+            if accepted != self.accepted {
+                self.pending.push_back(index);
+            }
+            self.accepted = accepted;
+            self.dropped = dropped;
+            self.stalled = 0;
+        }
+        if self.in_flight.is_none() {
+            self.ready = output.ready;
+        }
+
+        if !output.valid {
+            return Ok(None);
+        }
+        let index = self.pending.pop_front().ok_or("rezultat valid fara o intrare acceptata")?;
+        self.stalled = 0;
+        Ok(Some(index))
+    }
+}
+
+// rezultatul unui ciclu, pus pe pozitia intrarii careia ii apartine
+fn collect_result(
+    feed: &mut Feed,
+    output: &LidarOutput,
+    results: &mut [Option<CoreOutput>],
+) -> Result<(), Box<dyn Error>> {
+    let Some(index) = feed.observe(output)? else {
+        return Ok(());
+    };
+    let result = CoreOutput {
+        point: Some(output.corrected_point),
+        scan_done: output.scan_done,
+        pixel: output.pixel,
+        compact: output.compact,
+        ctx_id: output.ctx_id.raw() as u8,
+    };
+    check_context(&feed.inputs[index], &result)?;
+    results[index] = Some(result);
+    Ok(())
+}
+
+impl Backend for SimBackend {
+    fn process(&mut self, input: &LidarInput) -> Result<Option<CoreOutput>, Box<dyn Error>> {
+        Ok(self.process_batch(std::slice::from_ref(input))?.pop().flatten())
+    }
+
+    // toata scanarea printr-un singur LidarProcessor, punctele trimise prin handshake (vezi Feed)
+    // punctele merg back-to-back, deci simularea exercita si swap-urile de pose dintre ele
+    fn process_batch(&mut self, inputs: &[LidarInput]) -> Result<Vec<Option<CoreOutput>>, Box<dyn Error>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let acc_hdware = LidarProcessor::new(self.range_image).with_output_format(self.format);
+
+        // intrarile sunt generate ciclu cu ciclu, dupa iesirile observate
+        let feed = Rc::new(RefCell::new(Feed::new(inputs)));
+        let driver = Rc::clone(&feed);
+        let source = std::iter::from_fn(move || driver.borrow_mut().drive());
+
+        // .with_reset(1) -> reset activ in primul ciclu
+        let run = acc_hdware.run(source.with_reset(1).clock_pos_edge(100))?;
+
+        // citim un esantion pe ciclu (dupa frontul pozitiv), pe masura ce simularea avanseaza,
+        // si parcurgem toata rularea, ca sa citim si status-ul final al contoarelor
+        let mut results = vec![None; inputs.len()];
+        let mut last_status = None;
+        let mut prev_clock = false;
+        let mut on_sample = |sample: &TimedSample<(ClockReset, LidarInput, LidarOutput)>| {
+            // sample.value este (ClockReset, Input, Output)
+            let (cr, _, output) = sample.value;
+            let clock = cr.clock.raw();
+            let rising = clock && !prev_clock;
+            prev_clock = clock;
+            if !rising {
+                return;
+            }
+            last_status = Some(output.status);
+
+            let mut feed = feed.borrow_mut();
+            if let Err(e) = collect_result(&mut feed, &output, &mut results) {
+                // o eroare opreste alimentarea, deci si simularea
+                feed.error = Some(e);
+            }
+        };
+
+        // cu acoperirea activa, VCD-ul se strange din aceeasi rulare (vezi fpga_core::coverage)
+        match &mut self.coverage {
+            Some(cov) => cov.add_run(run.inspect(|sample| on_sample(sample)).collect::<Vcd>())?,
+            None => run.for_each(|sample| on_sample(&sample)),
+        }
+
+        let mut feed = feed.borrow_mut();
+        if let Some(e) = feed.error.take() {
+            return Err(e);
+        }
+        if !feed.finished() {
+            return Err(format!(
+                "simularea s-a blocat: {} puncte netrimise, {} fara rezultat",
+                inputs.len() - feed.next,
+                feed.pending.len() + usize::from(feed.in_flight.is_some())
+            ).into());
+        }
+
+        if let Some(status) = last_status {
+            self.perf.add(&status);
+        }
+        Ok(results)
+    }
+
+    fn perf(&self) -> Option<&PerfTotals> {
+        Some(&self.perf)
+    }
+
+    fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+}
@@ -0,0 +1,262 @@
+use fpga_core::bus::{INPUT_WORDS, OUTPUT_WORDS};
+use fpga_core::LidarInput;
+use fpga_core::types::{DebugSel, RotFixed};
+use rhdl::prelude::*;
+
+// impachetarea LidarInput / LidarOutput in cuvinte de magistrala
+// ordinea cuvintelor e documentata in fpga_core/src/bus.rs si trebuie pastrata identica
+
+// bitii din header-ul de intrare (cuvantul 0)
+pub const HDR_VALID: u32 = 1 << 0;
+pub const HDR_POSE_WRITE: u32 = 1 << 1;
+pub const HDR_POSE_SWAP: u32 = 1 << 2;
+pub const HDR_BIST_ENABLE: u32 = 1 << 3;
+pub const HDR_FIRST: u32 = 1 << 4;
+pub const HDR_LAST: u32 = 1 << 5;
+pub const HDR_POSE_COMPOSE: u32 = 1 << 6;
+// selectia de depanare, 2 biti
+pub const HDR_DEBUG_SHIFT: u32 = 7;
+pub const HDR_VELOCITY_MODE: u32 = 1 << 9;
+// contextul punctului si contextul in care se scrie pose-ul, cate 2 biti
+pub const HDR_CTX_SHIFT: u32 = 10;
+pub const HDR_POSE_CTX_SHIFT: u32 = 12;
+
+// bitii din header-ul de iesire folositi de host
+// (first/last, scan_count si bitii de BIST sunt pentru driver si bring-up)
+pub const OUT_VALID: u32 = 1 << 0;
+pub const OUT_SCAN_DONE: u32 = 1 << 3;
+pub const OUT_CTX_SHIFT: u32 = 4;
+// cuvintele 1..=3 poarta formatul compact (vezi fpga_core/src/format.rs)
+pub const OUT_COMPACT: u32 = 1 << 6;
+pub const OUT_COMPACT_WIDE: u32 = 1 << 7;
+pub const OUT_COMPACT_CM: u32 = 1 << 11;
+
+// un pachet de iesire decodat (coordonatele raman in 16.16 sau, cu compact, cuvintele formatului compact)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BusOutput {
+    pub valid: bool,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub scan_done: bool,
+    pub ctx_id: u8,
+    pub compact: bool,
+    pub compact_wide: bool,
+    pub compact_cm: bool,
+}
+
+// valori cu semn din RHDL -> cuvant de 32 biti (extins cu semn)
+fn fix_word(val: s32) -> u32 {
+    val.typed_bits().as_i64().unwrap() as i32 as u32
+}
+
+fn rot_word(val: RotFixed) -> u32 {
+    val.typed_bits().as_i64().unwrap() as i32 as u32
+}
+
+pub fn pack_input_words(input: &LidarInput) -> [u32; INPUT_WORDS] {
+    let mut words = [0u32; INPUT_WORDS];
+
+    let mut header = 0;
+    if input.valid { header |= HDR_VALID; }
+    if input.pose_write { header |= HDR_POSE_WRITE; }
+    if input.pose_swap { header |= HDR_POSE_SWAP; }
+    if input.bist_enable { header |= HDR_BIST_ENABLE; }
+    if input.first { header |= HDR_FIRST; }
+    if input.last { header |= HDR_LAST; }
+    if input.pose_compose { header |= HDR_POSE_COMPOSE; }
+    let debug_code = match input.debug_sel {
+        DebugSel::Final => 0,
+        DebugSel::Raw => 1,
+        DebugSel::Rotated => 2,
+    };
+    header |= debug_code << HDR_DEBUG_SHIFT;
+    if input.velocity_mode { header |= HDR_VELOCITY_MODE; }
+    header |= (input.ctx_id.raw() as u32) << HDR_CTX_SHIFT;
+    header |= (input.pose_ctx.raw() as u32) << HDR_POSE_CTX_SHIFT;
+    words[0] = header;
+
+    words[1] = fix_word(input.point.x);
+    words[2] = fix_word(input.point.y);
+    words[3] = fix_word(input.point.z);
+
+    for i in 0..3 {
+        for j in 0..3 {
+            words[4 + i * 3 + j] = rot_word(input.rotation.rows[i][j]);
+        }
+    }
+
+    words[13] = fix_word(input.translation.x);
+    words[14] = fix_word(input.translation.y);
+    words[15] = fix_word(input.translation.z);
+
+    // in modul cu viteze, cuvintele rotatiei poarta omega, velocity si dt
+    if input.velocity_mode {
+        words[4] = fix_word(input.omega.x);
+        words[5] = fix_word(input.omega.y);
+        words[6] = fix_word(input.omega.z);
+        words[7] = fix_word(input.velocity.x);
+        words[8] = fix_word(input.velocity.y);
+        words[9] = fix_word(input.velocity.z);
+        words[10] = fix_word(input.dt);
+    }
+
+    words
+}
+
+// pe 64 biti: cuvantul 2k jos, 2k+1 sus
+// (transportul serial lucreaza pe 32 biti, varianta asta e pentru placile cu magistrala lata)
+#[allow(dead_code)]
+pub fn pack_input_words64(input: &LidarInput) -> [u64; INPUT_WORDS / 2] {
+    let words = pack_input_words(input);
+    let mut beats = [0u64; INPUT_WORDS / 2];
+    for (k, beat) in beats.iter_mut().enumerate() {
+        *beat = words[2 * k] as u64 | ((words[2 * k + 1] as u64) << 32);
+    }
+    beats
+}
+
+pub fn unpack_output_words(words: &[u32; OUTPUT_WORDS]) -> BusOutput {
+    let header = words[0];
+    BusOutput {
+        valid: header & OUT_VALID != 0,
+        x: words[1] as i32,
+        y: words[2] as i32,
+        z: words[3] as i32,
+        scan_done: header & OUT_SCAN_DONE != 0,
+        ctx_id: ((header >> OUT_CTX_SHIFT) & 3) as u8,
+        compact: header & OUT_COMPACT != 0,
+        compact_wide: header & OUT_COMPACT_WIDE != 0,
+        compact_cm: header & OUT_COMPACT_CM != 0,
+    }
+}
+
+#[allow(dead_code)]
+pub fn unpack_output_words64(beats: &[u64; OUTPUT_WORDS / 2]) -> BusOutput {
+    let mut words = [0u32; OUTPUT_WORDS];
+    for (k, beat) in beats.iter().enumerate() {
+        words[2 * k] = *beat as u32;
+        words[2 * k + 1] = (*beat >> 32) as u32;
+    }
+    unpack_output_words(&words)
+}
+
+// packer-ul trebuie sa dea aceleasi cuvinte ca functiile din hardware (input_word / output_word),
+// altfel host-ul si placa nu se mai inteleg, fara ca vreo simulare sa observe
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fpga_core::bus::{input_word, output_word};
+    use fpga_core::types::Vector3;
+    use fpga_core::{CompactPoint, CompactUnit, LidarOutput, Matrix3x3};
+
+    fn fix(v: i32) -> s32 {
+        s32::from(v as i128)
+    }
+
+    // toate bitii din header si valori diferite (si negative) in fiecare cuvant
+    fn sample_input(velocity_mode: bool) -> LidarInput {
+        let mut rows = [[RotFixed::default(); 3]; 3];
+        for (k, coef) in rows.iter_mut().flatten().enumerate() {
+            *coef = RotFixed::from(k as i128 * 30_011 - 120_000);
+        }
+        LidarInput {
+            valid: true,
+            point: Vector3 { x: fix(-123_456), y: fix(65_536), z: fix(i32::MIN) },
+            rotation: Matrix3x3 { rows },
+            translation: Vector3 { x: fix(7), y: fix(-8), z: fix(i32::MAX) },
+            pose_write: true,
+            pose_swap: true,
+            bist_enable: true,
+            first: true,
+            last: true,
+            pose_compose: true,
+            debug_sel: DebugSel::Rotated,
+            velocity_mode,
+            omega: Vector3 { x: fix(11), y: fix(-12), z: fix(13) },
+            velocity: Vector3 { x: fix(-21), y: fix(22), z: fix(-23) },
+            dt: fix(4321),
+            ctx_id: b2::from(2u128),
+            pose_ctx: b2::from(3u128),
+        }
+    }
+
+    fn hw_input_words(input: &LidarInput) -> Vec<u32> {
+        (0..INPUT_WORDS).map(|k| input_word(*input, b5::from(k as u128)).raw() as u32).collect()
+    }
+
+    fn hw_output_words(output: &LidarOutput) -> [u32; OUTPUT_WORDS] {
+        std::array::from_fn(|k| output_word(*output, b3::from(k as u128)).raw() as u32)
+    }
+
+    #[test]
+    fn input_words_match_hardware() {
+        for velocity_mode in [false, true] {
+            let input = sample_input(velocity_mode);
+            assert_eq!(pack_input_words(&input).to_vec(), hw_input_words(&input), "velocity_mode = {}", velocity_mode);
+        }
+    }
+
+    #[test]
+    fn input_beats64_match_hardware() {
+        let input = sample_input(false);
+        let words = hw_input_words(&input);
+        for (k, beat) in pack_input_words64(&input).iter().enumerate() {
+            assert_eq!(*beat, words[2 * k] as u64 | ((words[2 * k + 1] as u64) << 32), "beat {}", k);
+        }
+    }
+
+    #[test]
+    fn output_words_decode_like_hardware() {
+        let output = LidarOutput {
+            valid: true,
+            corrected_point: Vector3 { x: fix(-5), y: fix(655_360), z: fix(i32::MIN) },
+            ctx_id: b2::from(1u128),
+            scan_done: true,
+            scan_count: b32::from(3u128),
+            ..LidarOutput::default()
+        };
+        let decoded = unpack_output_words(&hw_output_words(&output));
+        assert_eq!(decoded, BusOutput {
+            valid: true,
+            x: -5,
+            y: 655_360,
+            z: i32::MIN,
+            scan_done: true,
+            ctx_id: 1,
+            compact: false,
+            compact_wide: false,
+            compact_cm: false,
+        });
+
+        // cu formatul compact, cuvintele 1..=3 sunt bitii din CompactPoint.data
+        let compact = LidarOutput {
+            compact: CompactPoint {
+                valid: true,
+                wide: true,
+                unit: CompactUnit::Centimetre,
+                data: b96::from(0x0000_0003_FFFF_FFFE_0000_0001u128),
+            },
+            ..output
+        };
+        let decoded = unpack_output_words(&hw_output_words(&compact));
+        assert!(decoded.compact && decoded.compact_wide && decoded.compact_cm);
+        assert_eq!((decoded.x, decoded.y, decoded.z), (1, -2, 3));
+    }
+
+    #[test]
+    fn output_beats64_decode_like_words() {
+        let output = LidarOutput {
+            valid: true,
+            corrected_point: Vector3 { x: fix(1), y: fix(-2), z: fix(3) },
+            ctx_id: b2::from(3u128),
+            ..LidarOutput::default()
+        };
+        let words = hw_output_words(&output);
+        let beats = [
+            words[0] as u64 | ((words[1] as u64) << 32),
+            words[2] as u64 | ((words[3] as u64) << 32),
+        ];
+        assert_eq!(unpack_output_words64(&beats), unpack_output_words(&words));
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use fpga_core::{CompactUnit, OutputFormat};
+use fpga_core::types::DebugSel;
+
+// linia de comanda a host_software
+//   correct  - corectia unei scanari (pipeline-ul complet: IMU -> traiectorie -> hardware -> CSV)
+//   inspect  - rezumatul fisierelor de intrare (intervale de timp, rate, scanari)
+//   compare  - diferentele punct cu punct intre doi nori de puncte CSV
+#[derive(Debug, Parser)]
+#[command(name = "host_software", about = "Corectia de miscare LiDAR cu acceleratorul FPGA")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    Correct(CorrectArgs),
+    Inspect(InspectArgs),
+    Compare(CompareArgs),
+}
+
+// fisierele de intrare, comune pentru correct si inspect
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    #[arg(long, default_value = "data/raw_imu.csv")]
+    pub imu: PathBuf,
+    #[arg(long, default_value = "data/raw_lidar.csv")]
+    pub lidar: PathBuf,
+}
+
+// ce scanare se proceseaza
+#[derive(Debug, Args)]
+pub struct ScanArgs {
+    /// prima scanare incepe dupa acest timestamp (implicit: inceputul IMU-ului + 1 ms)
+    #[arg(long)]
+    pub start_ns: Option<u64>,
+    /// cate scanari se sar dupa start
+    #[arg(long, default_value_t = 0)]
+    pub scan_index: usize,
+    /// durata unei scanari (10 Hz = 100 ms)
+    #[arg(long, default_value_t = 100)]
+    pub scan_duration_ms: u64,
+}
+
+// sistemul de referinta al punctelor corectate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Frame {
+    /// originea traiectoriei: primul esantion IMU cu --all-scans, altfel inceputul ferestrei IMU
+    /// (t_start - imu_margin), deci doua scanari rulate separat nu au aceeasi origine
+    World,
+    /// senzorul la inceputul scanarii
+    ScanStart,
+    /// senzorul la sfarsitul scanarii
+    ScanEnd,
+}
+
+// --debug: ce intoarce hardware-ul in coloanele x,y,z din CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DebugArg {
+    /// R * P + T (implicit)
+    Final,
+    /// punctul de intrare
+    Raw,
+    /// doar R * P
+    Rotated,
+}
+
+impl From<DebugArg> for DebugSel {
+    fn from(arg: DebugArg) -> Self {
+        match arg {
+            DebugArg::Final => DebugSel::Final,
+            DebugArg::Raw => DebugSel::Raw,
+            DebugArg::Rotated => DebugSel::Rotated,
+        }
+    }
+}
+
+// --compact: coordonatele intregi din etajul de formatare (fpga_core/src/format.rs), in loc de 16.16
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompactArg {
+    /// milimetri pe 32 de biti
+    Mm32,
+    /// milimetri pe 16 biti (saturat la +-32 m)
+    Mm16,
+    /// centimetri pe 16 biti (saturat la +-327 m)
+    Cm16,
+}
+
+impl From<CompactArg> for OutputFormat {
+    fn from(arg: CompactArg) -> Self {
+        match arg {
+            CompactArg::Mm32 => OutputFormat::new(CompactUnit::Millimetre, true),
+            CompactArg::Mm16 => OutputFormat::new(CompactUnit::Millimetre, false),
+            CompactArg::Cm16 => OutputFormat::new(CompactUnit::Centimetre, false),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CorrectArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    /// norul corectat (x,y,z,intensity); formatul dupa extensie: .csv, .pcd (PCL), .ply sau .las (LAS 1.4)
+    #[arg(long, short, default_value = "data/corrected_cloud.csv")]
+    pub output: PathBuf,
+    /// PCD / PLY binar (little-endian) in loc de ASCII
+    #[arg(long)]
+    pub binary: bool,
+    /// adauga campul 'error': distanta pana la corectia de referinta f64, in metri
+    #[arg(long)]
+    pub error_field: bool,
+    /// imaginea de distante, scrisa doar daca backend-ul intoarce pixeli
+    #[arg(long, default_value = "data/range_image.pgm")]
+    pub range_image: PathBuf,
+
+    /// sim (implicit), uart-sim sau uart:<port>
+    #[arg(long, short, default_value = "sim")]
+    pub backend: String,
+    /// cati workeri de backend ruleaza in paralel cu --all-scans (implicit numarul de nuclee;
+    /// uart:<port> si modul cu o singura scanare folosesc unul)
+    #[arg(long, short)]
+    pub jobs: Option<usize>,
+    #[arg(long, value_enum, default_value_t = Frame::World)]
+    pub frame: Frame,
+    /// marginea de IMU pastrata in jurul scanarii, pentru interpolare
+    #[arg(long, default_value_t = 5)]
+    pub imu_margin_ms: u64,
+
+    /// hardware-ul intoarce coordonate intregi (mm / cm) in loc de 16.16 (doar sim); rotunjirea intra in eroare
+    #[arg(long, value_enum)]
+    pub compact: Option<CompactArg>,
+    /// acoperirea automatului si a registrelor din engine (doar sim, mult mai lent)
+    #[arg(long)]
+    pub coverage: bool,
+    #[arg(long, value_enum, default_value_t = DebugArg::Final)]
+    pub debug: DebugArg,
+    /// viteze constante pe scanare in loc de un pose per punct (rezultatul e in sistemul scan-start)
+    #[arg(long)]
+    pub velocity: bool,
+
+    /// raportul de precizie fata de corectia de referinta in f64 (JSON pentru .json, altfel CSV)
+    #[arg(long)]
+    pub accuracy_report: Option<PathBuf>,
+    /// latimea unui bin din histograma erorilor, in milimetri
+    #[arg(long, default_value_t = 0.1, value_parser = parse_positive)]
+    pub accuracy_bin_mm: f64,
+
+    /// toata inregistrarea, scanare cu scanare, incepand cu --scan-index (CSV-ul primeste coloana 'scan')
+    #[arg(long)]
+    pub all_scans: bool,
+    /// cu --all-scans: cate un fisier pe scanare (cloud_0000.csv, cloud_0001.csv ...) in loc de coloana 'scan'
+    #[arg(long, requires = "all_scans")]
+    pub split_scans: bool,
+    /// cu --all-scans: numarul maxim de scanari procesate
+    #[arg(long, requires = "all_scans")]
+    pub max_scans: Option<usize>,
+}
+
+// un numar strict pozitiv si finit (latimi, pasi)
+fn parse_positive(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|e| format!("{}", e))?;
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("trebuie sa fie un numar pozitiv, nu {}", s))
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct InspectArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[arg(long, default_value_t = 100)]
+    pub scan_duration_ms: u64,
+    /// cate scanari se listeaza (fisierele LiDAR pot avea zeci de GB, citirea se opreste dupa ele)
+    #[arg(long, default_value_t = 10)]
+    pub max_scans: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    /// doi nori CSV cu coloanele x,y,z (ex: rularea sim si rularea pe placa)
+    pub first: PathBuf,
+    pub second: PathBuf,
+    /// pragul peste care un punct e raportat ca diferit (metri)
+    #[arg(long, default_value_t = 0.001, value_parser = parse_positive)]
+    pub tolerance: f64,
+}
@@ -0,0 +1,322 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::las_writer::LasWriter;
+
+// scrierea norului corectat, in formatul dat de extensia fisierului:
+//   .pcd - PCL (ASCII sau binar), .ply - PLY (ASCII sau binar little-endian), .las - LAS 1.4 (las_writer.rs), altfel CSV
+// campurile sunt x, y, z, intensity + campurile optionale din CloudLayout
+// numarul de puncte nu e cunoscut dinainte (scanarile vin in flux), deci in antet se scrie un camp de
+// lungime fixa care e completat la finish()
+
+// cifrele campului cu numarul de puncte din antet (u32 incape in 10)
+const COUNT_DIGITS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudFormat {
+    Csv,
+    Pcd { binary: bool },
+    Ply { binary: bool },
+    Las,
+}
+
+impl CloudFormat {
+    // formatul dupa extensie; 'binary' conteaza doar pentru PCD / PLY
+    pub fn from_path(path: &Path, binary: bool) -> Result<Self, Box<dyn Error>> {
+        let ext = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("pcd") => Ok(CloudFormat::Pcd { binary }),
+            Some("ply") => Ok(CloudFormat::Ply { binary }),
+            Some("las") => Ok(CloudFormat::Las),
+            // LAZ e LAS comprimat cu LASzip; fisierul .las se converteste cu 'laszip -i cloud.las -o cloud.laz'
+            Some("laz") => Err("LAZ nu e suportat direct: scrieti .las si comprimati cu laszip".into()),
+            _ => Ok(CloudFormat::Csv),
+        }
+    }
+}
+
+// campurile optionale, dupa x, y, z, intensity
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CloudLayout {
+    // indexul scanarii (--all-scans fara --split-scans); in CSV e prima coloana, ca pana acum
+    pub scan: bool,
+    // distanta pana la corectia de referinta f64, in metri
+    pub error: bool,
+}
+
+// un punct corectat, gata de scris
+#[derive(Debug, Clone, Copy)]
+pub struct CloudPoint {
+    pub xyz: [f64; 3],
+    pub intensity: u8,
+    pub scan: u32,
+    pub error: f32,
+    // momentul masurarii punctului (GPS time in LAS)
+    pub timestamp_ns: u64,
+}
+
+// LAS are antetul si codificarea lui; celelalte formate trec prin TextCloud (CSV, PCD, PLY)
+pub enum CloudWriter {
+    Text(TextCloud),
+    Las(LasWriter),
+}
+
+impl CloudWriter {
+    pub fn create(path: impl AsRef<Path>, format: CloudFormat, layout: CloudLayout) -> Result<Self, Box<dyn Error>> {
+        match format {
+            // campurile optionale nu au loc in formatul 6: scanarea ajunge in point source id
+            CloudFormat::Las => Ok(CloudWriter::Las(LasWriter::create(path)?)),
+            _ => Ok(CloudWriter::Text(TextCloud::create(path, format, layout)?)),
+        }
+    }
+
+    pub fn write_point(&mut self, p: &CloudPoint) -> Result<(), Box<dyn Error>> {
+        match self {
+            CloudWriter::Text(w) => w.write_point(p),
+            CloudWriter::Las(w) => w.write_point(p),
+        }
+    }
+
+    // numarul de puncte scrise
+    pub fn finish(self) -> Result<u64, Box<dyn Error>> {
+        match self {
+            CloudWriter::Text(w) => w.finish(),
+            CloudWriter::Las(w) => w.finish(),
+        }
+    }
+}
+
+pub struct TextCloud {
+    out: BufWriter<File>,
+    format: CloudFormat,
+    layout: CloudLayout,
+    count: u64,
+    // pozitiile din antet unde se scrie numarul de puncte
+    count_offsets: Vec<u64>,
+}
+
+impl TextCloud {
+    fn create(path: impl AsRef<Path>, format: CloudFormat, layout: CloudLayout) -> Result<Self, Box<dyn Error>> {
+        let mut writer = Self {
+            out: BufWriter::new(File::create(path.as_ref())?),
+            format,
+            layout,
+            count: 0,
+            count_offsets: Vec::new(),
+        };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    // numele campurilor, in ordinea din fisier (fara coloana 'scan' din CSV)
+    fn field_names(&self) -> Vec<&'static str> {
+        let mut names = vec!["x", "y", "z", "intensity"];
+        if self.layout.scan {
+            names.push("scan");
+        }
+        if self.layout.error {
+            names.push("error");
+        }
+        names
+    }
+
+    // un placeholder de lungime fixa pentru numarul de puncte, completat la finish()
+    fn write_count_placeholder(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        self.count_offsets.push(self.out.get_mut().stream_position()?);
+        write!(self.out, "{:0width$}", 0, width = COUNT_DIGITS)?;
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.format {
+            CloudFormat::Csv => {
+                let mut columns = Vec::new();
+                if self.layout.scan {
+                    columns.push("scan");
+                }
+                columns.extend(["x", "y", "z", "intensity"]);
+                if self.layout.error {
+                    columns.push("error");
+                }
+                writeln!(self.out, "{}", columns.join(","))?;
+            }
+            CloudFormat::Pcd { binary } => {
+                // campurile float, ca PointXYZI din PCL; scan e intreg fara semn
+                let names = self.field_names();
+                let types: Vec<&str> = names.iter().map(|&n| if n == "scan" { "U" } else { "F" }).collect();
+                writeln!(self.out, "# .PCD v0.7 - Point Cloud Data file format")?;
+                writeln!(self.out, "VERSION 0.7")?;
+                writeln!(self.out, "FIELDS {}", names.join(" "))?;
+                writeln!(self.out, "SIZE {}", vec!["4"; names.len()].join(" "))?;
+                writeln!(self.out, "TYPE {}", types.join(" "))?;
+                writeln!(self.out, "COUNT {}", vec!["1"; names.len()].join(" "))?;
+                write!(self.out, "WIDTH ")?;
+                self.write_count_placeholder()?;
+                writeln!(self.out)?;
+                writeln!(self.out, "HEIGHT 1")?;
+                writeln!(self.out, "VIEWPOINT 0 0 0 1 0 0 0")?;
+                write!(self.out, "POINTS ")?;
+                self.write_count_placeholder()?;
+                writeln!(self.out)?;
+                writeln!(self.out, "DATA {}", if binary { "binary" } else { "ascii" })?;
+            }
+            CloudFormat::Ply { binary } => {
+                writeln!(self.out, "ply")?;
+                writeln!(self.out, "format {} 1.0", if binary { "binary_little_endian" } else { "ascii" })?;
+                writeln!(self.out, "comment LiDAR motion correction - corrected cloud")?;
+                write!(self.out, "element vertex ")?;
+                self.write_count_placeholder()?;
+                writeln!(self.out)?;
+                for name in self.field_names() {
+                    let kind = if name == "scan" { "uint" } else { "float" };
+                    writeln!(self.out, "property {} {}", kind, name)?;
+                }
+                writeln!(self.out, "end_header")?;
+            }
+            CloudFormat::Las => unreachable!("LAS se scrie prin LasWriter"),
+        }
+        Ok(())
+    }
+
+    fn write_point(&mut self, p: &CloudPoint) -> Result<(), Box<dyn Error>> {
+        let [x, y, z] = p.xyz;
+        match self.format {
+            CloudFormat::Csv => {
+                if self.layout.scan {
+                    write!(self.out, "{},", p.scan)?;
+                }
+                write!(self.out, "{},{},{},{}", x, y, z, p.intensity)?;
+                if self.layout.error {
+                    write!(self.out, ",{}", p.error)?;
+                }
+                writeln!(self.out)?;
+            }
+            CloudFormat::Pcd { binary: false } | CloudFormat::Ply { binary: false } => {
+                write!(self.out, "{} {} {} {}", x as f32, y as f32, z as f32, p.intensity)?;
+                if self.layout.scan {
+                    write!(self.out, " {}", p.scan)?;
+                }
+                if self.layout.error {
+                    write!(self.out, " {}", p.error)?;
+                }
+                writeln!(self.out)?;
+            }
+            // PCD binar e tot little-endian (ordinea nativa pe x86 / ARM)
+            CloudFormat::Pcd { binary: true } | CloudFormat::Ply { binary: true } => {
+                for v in [x as f32, y as f32, z as f32, p.intensity as f32] {
+                    self.out.write_all(&v.to_le_bytes())?;
+                }
+                if self.layout.scan {
+                    self.out.write_all(&p.scan.to_le_bytes())?;
+                }
+                if self.layout.error {
+                    self.out.write_all(&p.error.to_le_bytes())?;
+                }
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    // completeaza numarul de puncte in antet si inchide fisierul
+    fn finish(mut self) -> Result<u64, Box<dyn Error>> {
+        if self.count >= 10u64.pow(COUNT_DIGITS as u32) {
+            return Err(format!("Prea multe puncte pentru un singur fisier: {}", self.count).into());
+        }
+        self.out.flush()?;
+        let file = self.out.get_mut();
+        for &offset in &self.count_offsets {
+            file.seek(SeekFrom::Start(offset))?;
+            write!(file, "{:0width$}", self.count, width = COUNT_DIGITS)?;
+        }
+        file.flush()?;
+        Ok(self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("cloud_writer_{}_{}", std::process::id(), name))
+    }
+
+    // scrie doua puncte si intoarce continutul fisierului
+    fn write_two(name: &str, layout: CloudLayout, binary: bool) -> Vec<u8> {
+        let path = temp_path(name);
+        let format = CloudFormat::from_path(&path, binary).unwrap();
+        let mut cloud = CloudWriter::create(&path, format, layout).unwrap();
+        for k in 0..2u8 {
+            let p = CloudPoint { xyz: [1.5, -2.0, k as f64], intensity: 10 + k, scan: 7, error: 0.25, timestamp_ns: 0 };
+            cloud.write_point(&p).unwrap();
+        }
+        assert_eq!(cloud.finish().unwrap(), 2);
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        bytes
+    }
+
+    // antetul pana la linia care incepe cu 'last' (inclusiv) si datele de dupa el
+    fn split_header<'a>(bytes: &'a [u8], last: &str) -> (&'a str, &'a [u8]) {
+        let text = String::from_utf8_lossy(bytes);
+        let start = text.find(last).expect("antet incomplet");
+        let end = start + text[start..].find('\n').unwrap() + 1;
+        (std::str::from_utf8(&bytes[..end]).unwrap(), &bytes[end..])
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(CloudFormat::from_path(Path::new("a.PCD"), true).unwrap(), CloudFormat::Pcd { binary: true });
+        assert_eq!(CloudFormat::from_path(Path::new("a.ply"), false).unwrap(), CloudFormat::Ply { binary: false });
+        assert_eq!(CloudFormat::from_path(Path::new("a.las"), false).unwrap(), CloudFormat::Las);
+        assert_eq!(CloudFormat::from_path(Path::new("a.txt"), true).unwrap(), CloudFormat::Csv);
+        assert!(CloudFormat::from_path(Path::new("a.laz"), false).is_err());
+    }
+
+    #[test]
+    fn csv_columns_and_rows() {
+        let bytes = write_two("rows.csv", CloudLayout { scan: true, error: true }, false);
+        let text = String::from_utf8(bytes).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines, ["scan,x,y,z,intensity,error", "7,1.5,-2,0,10,0.25", "7,1.5,-2,1,11,0.25"]);
+    }
+
+    #[test]
+    fn pcd_binary_count_patched_and_record_size() {
+        let bytes = write_two("binary.pcd", CloudLayout { scan: true, error: true }, true);
+        let (header, data) = split_header(&bytes, "DATA");
+        assert!(header.contains("FIELDS x y z intensity scan error\n"), "{}", header);
+        assert!(header.contains("TYPE F F F F U F\n"), "{}", header);
+        assert!(header.contains("WIDTH 0000000002\n"), "{}", header);
+        assert!(header.contains("POINTS 0000000002\n"), "{}", header);
+        assert!(header.ends_with("DATA binary\n"));
+        // 6 campuri de 4 octeti pe punct
+        assert_eq!(data.len(), 2 * 6 * 4);
+        assert_eq!(f32::from_le_bytes(data[0..4].try_into().unwrap()), 1.5);
+        assert_eq!(u32::from_le_bytes(data[16..20].try_into().unwrap()), 7);
+    }
+
+    #[test]
+    fn ply_binary_count_patched_and_record_size() {
+        let bytes = write_two("binary.ply", CloudLayout::default(), true);
+        let (header, data) = split_header(&bytes, "end_header");
+        assert!(header.starts_with("ply\nformat binary_little_endian 1.0\n"), "{}", header);
+        assert!(header.contains("element vertex 0000000002\n"), "{}", header);
+        // x, y, z, intensity ca float
+        assert_eq!(data.len(), 2 * 4 * 4);
+        assert_eq!(f32::from_le_bytes(data[28..32].try_into().unwrap()), 11.0);
+    }
+
+    #[test]
+    fn ply_ascii_rows() {
+        let bytes = write_two("ascii.ply", CloudLayout { scan: false, error: true }, false);
+        let (header, data) = split_header(&bytes, "end_header");
+        assert!(header.contains("format ascii 1.0\n"));
+        assert!(header.contains("property float error\n"));
+        assert_eq!(std::str::from_utf8(data).unwrap(), "1.5 -2 0 10 0.25\n1.5 -2 1 11 0.25\n");
+    }
+}
@@ -0,0 +1,65 @@
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::cli::CompareArgs;
+
+// doar coordonatele, restul coloanelor (intensity etc.) sunt ignorate
+#[derive(Debug, Deserialize)]
+struct CloudPoint {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+fn load_cloud(path: &Path) -> Result<Vec<CloudPoint>, Box<dyn Error>> {
+    let mut rdr = csv::Reader::from_reader(File::open(path)?);
+    let mut points = Vec::new();
+    for result in rdr.deserialize() {
+        points.push(result?);
+    }
+    Ok(points)
+}
+
+// compare: distanta punct cu punct intre doi nori cu aceeasi ordine a punctelor
+pub fn run(args: &CompareArgs) -> Result<(), Box<dyn Error>> {
+    let first = load_cloud(&args.first)?;
+    let second = load_cloud(&args.second)?;
+
+    println!("{}: {} puncte", args.first.display(), first.len());
+    println!("{}: {} puncte", args.second.display(), second.len());
+    if first.len() != second.len() {
+        println!("ATENTIE: numar diferit de puncte, se compara primele {}", first.len().min(second.len()));
+    }
+
+    let distances: Vec<f64> = first.iter()
+        .zip(&second)
+        .map(|(a, b)| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt())
+        .collect();
+    if distances.is_empty() {
+        return Err("Nu exista puncte de comparat".into());
+    }
+
+    let count = distances.len() as f64;
+    let mean = distances.iter().sum::<f64>() / count;
+    let rms = (distances.iter().map(|d| d * d).sum::<f64>() / count).sqrt();
+    let (worst_idx, worst) = distances.iter()
+        .copied()
+        .enumerate()
+        .fold((0, 0.0), |acc, (i, d)| if d > acc.1 { (i, d) } else { acc });
+    let over: Vec<usize> = distances.iter()
+        .enumerate()
+        .filter(|(_, &d)| d > args.tolerance)
+        .map(|(i, _)| i)
+        .collect();
+
+    println!("Distanta medie: {:.6} m, RMS: {:.6} m, maxima: {:.6} m (punctul {})", mean, rms, worst, worst_idx);
+    println!("Peste {} m: {} / {} puncte", args.tolerance, over.len(), distances.len());
+    if !over.is_empty() {
+        println!("  primele: {:?}", &over[..over.len().min(10)]);
+    }
+
+    Ok(())
+}
@@ -1,84 +1,211 @@
-use serde::Deserialize;
-use std::error::Error;
-use std::fs::File;
-use std::path::Path;
-
-#[derive(Debug, Deserialize)]
-pub struct ImuMeasurement {
-    pub timestamp_ns: u64,
-    pub ang_vel_x: f64,
-    pub ang_vel_y: f64,
-    pub ang_vel_z: f64,
-    pub acc_x: f64,
-    pub acc_y: f64,
-    pub acc_z: f64,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct LidarPoint {
-    pub timestamp_ns: u64,
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
-    pub intensity: u8,
-}
-
-pub fn load_imu_data(path: &str) -> Result<Vec<ImuMeasurement>, Box<dyn Error>> {
-    let file_path = Path::new(path);
-    let file = File::open(file_path)?;
-    let mut rdr = csv::Reader::from_reader(file);
-    let mut data = Vec::new();
-
-    for result in rdr.deserialize() {
-        data.push(result?);
-    }
-    Ok(data)
-}
-
-
-// CITIM doar o singură scanare (100ms) și se oprește.
-pub fn load_single_scan(path: &str, min_timestamp: u64) -> Result<Vec<LidarPoint>, Box<dyn Error>> {
-    let file_path = Path::new(path);
-    if !file_path.exists() {
-        return Err(format!("Nu gasesc fisierul: {}", path).into());
-    }
-
-    let file = File::open(file_path)?;
-    let mut rdr = csv::Reader::from_reader(file);
-    let mut scan_points = Vec::new();
-
-    let mut start_ts = 0;
-    const SCAN_DURATION_NS: u64 = 100_000_000; // 100ms
-
-    println!("Cautam prima scanare valida dupa t={}", min_timestamp);
-
-    for result in rdr.deserialize() {
-        let point: LidarPoint = result?;
-
-        // 1. Sărim la inceputul IMU-lui
-        if point.timestamp_ns < min_timestamp {
-            continue;
-        }
-
-        // 2. inceputul imu a fost gasit
-        if start_ts == 0 {
-            start_ts = point.timestamp_ns;
-            println!("Sincronizare reusita! Incepem scanarea la t={}", start_ts);
-        }
-
-        // 3. Verificăm dacă a inceput o noua scanare
-        if point.timestamp_ns > start_ts + SCAN_DURATION_NS {
-            break;
-        }
-
-        scan_points.push(point);
-    }
-
-    // magie rust
-    if scan_points.is_empty() {
-        return Err("Nu s-au gasit puncte LiDAR după timestamp.".into());
-    }
-
-    scan_points.shrink_to_fit();
-    Ok(scan_points)
-}
\ No newline at end of file
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImuMeasurement {
+    pub timestamp_ns: u64,
+    pub ang_vel_x: f64,
+    pub ang_vel_y: f64,
+    pub ang_vel_z: f64,
+    pub acc_x: f64,
+    pub acc_y: f64,
+    pub acc_z: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LidarPoint {
+    pub timestamp_ns: u64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub intensity: u8,
+}
+
+// masuratorile IMU pe rand, fara sa incarcam tot fisierul
+pub fn imu_records(path: impl AsRef<Path>) -> Result<csv::DeserializeRecordsIntoIter<File, ImuMeasurement>, Box<dyn Error>> {
+    let file = File::open(path.as_ref())?;
+    Ok(csv::Reader::from_reader(file).into_deserialize())
+}
+
+// fereastra glisanta peste fisierul IMU: citim doar pana unde avem nevoie si uitam ce a ramas in urma
+// memoria ramane proportionala cu fereastra, nu cu lungimea inregistrarii
+pub struct ImuWindow {
+    records: csv::DeserializeRecordsIntoIter<File, ImuMeasurement>,
+    window: VecDeque<ImuMeasurement>,
+    exhausted: bool,
+}
+
+impl ImuWindow {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(Self { records: imu_records(path)?, window: VecDeque::new(), exhausted: false })
+    }
+
+    // o masuratoare noua la capatul ferestrei; false la sfarsitul fisierului
+    fn read_one(&mut self) -> Result<bool, Box<dyn Error>> {
+        if self.exhausted {
+            return Ok(false);
+        }
+        match self.records.next() {
+            Some(result) => {
+                self.window.push_back(result?);
+                Ok(true)
+            }
+            None => {
+                self.exhausted = true;
+                Ok(false)
+            }
+        }
+    }
+
+    // timestamp-ul primei masuratori din fereastra (citeste una daca fereastra e goala)
+    pub fn first_timestamp(&mut self) -> Result<Option<u64>, Box<dyn Error>> {
+        if self.window.is_empty() {
+            self.read_one()?;
+        }
+        Ok(self.window.front().map(|m| m.timestamp_ns))
+    }
+
+    // citim pana trecem de 't' (o masuratoare dupa 't', pentru interpolare)
+    // intoarce cate masuratori noi au intrat; ele sunt ultimele din window()
+    pub fn fill_until(&mut self, t: u64) -> Result<usize, Box<dyn Error>> {
+        let mut added = 0;
+        while !matches!(self.window.back(), Some(m) if m.timestamp_ns > t) && self.read_one()? {
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    // uitam masuratorile de dinainte de 't'
+    pub fn drop_before(&mut self, t: u64) {
+        while self.window.front().is_some_and(|m| m.timestamp_ns < t) {
+            self.window.pop_front();
+        }
+    }
+
+    pub fn window(&mut self) -> &[ImuMeasurement] {
+        self.window.make_contiguous()
+    }
+
+    // masuratorile din fereastra cu timestamp-ul in [t0, t1]
+    pub fn between(&mut self, t0: u64, t1: u64) -> &[ImuMeasurement] {
+        let window = self.window.make_contiguous();
+        let lo = window.partition_point(|m| m.timestamp_ns < t0);
+        let hi = window.partition_point(|m| m.timestamp_ns <= t1);
+        &window[lo..hi.max(lo)]
+    }
+}
+
+// ce scanare citim din fisierul LiDAR
+#[derive(Debug, Clone, Copy)]
+pub struct ScanSelection {
+    // prima scanare incepe la primul punct de dupa acest timestamp
+    pub min_timestamp: u64,
+    // cate scanari complete se sar
+    pub skip_scans: usize,
+    pub duration_ns: u64,
+}
+
+// o scanare: indexul ei (de la 'min_timestamp') si punctele
+#[derive(Debug)]
+pub struct Scan {
+    pub index: usize,
+    pub points: Vec<LidarPoint>,
+}
+
+// scanarile de dupa 'min_timestamp', citite lazy dintr-un singur reader deschis
+// o scanare noua incepe cu primul punct aflat la mai mult de 'duration_ns' de inceputul celei curente
+// in memorie e doar scanarea curenta, oricat de lung ar fi fisierul
+pub struct ScanReader {
+    records: csv::DeserializeRecordsIntoIter<File, LidarPoint>,
+    min_timestamp: u64,
+    duration_ns: u64,
+    // primul punct al scanarii urmatoare, citit deja
+    pending: Option<LidarPoint>,
+    index: usize,
+    done: bool,
+}
+
+impl ScanReader {
+    pub fn open(path: impl AsRef<Path>, min_timestamp: u64, duration_ns: u64) -> Result<Self, Box<dyn Error>> {
+        let file_path = path.as_ref();
+        if !file_path.exists() {
+            return Err(format!("Nu gasesc fisierul: {}", file_path.display()).into());
+        }
+        let file = File::open(file_path)?;
+        Ok(Self {
+            records: csv::Reader::from_reader(file).into_deserialize(),
+            min_timestamp,
+            duration_ns,
+            pending: None,
+            index: 0,
+            done: false,
+        })
+    }
+}
+
+impl Iterator for ScanReader {
+    type Item = Result<Scan, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done && self.pending.is_none() {
+            return None;
+        }
+
+        let mut points: Vec<LidarPoint> = self.pending.take().into_iter().collect();
+        while !self.done {
+            let point: LidarPoint = match self.records.next() {
+                Some(Ok(point)) => point,
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+                None => {
+                    self.done = true;
+                    break;
+                }
+            };
+
+            // sarim tot ce e inainte de inceputul IMU-ului
+            if point.timestamp_ns < self.min_timestamp {
+                continue;
+            }
+
+            // a inceput o scanare noua: punctul asteapta urmatorul apel
+            if points.first().is_some_and(|first| point.timestamp_ns > first.timestamp_ns + self.duration_ns) {
+                self.pending = Some(point);
+                break;
+            }
+            points.push(point);
+        }
+
+        // ultima scanare poate fi incompleta
+        if points.is_empty() {
+            return None;
+        }
+        let scan = Scan { index: self.index, points };
+        self.index += 1;
+        Some(Ok(scan))
+    }
+}
+
+// CITIM doar o singură scanare și ne oprim.
+pub fn load_scan(path: impl AsRef<Path>, selection: &ScanSelection) -> Result<Vec<LidarPoint>, Box<dyn Error>> {
+    println!("Cautam scanarea {} dupa t={}", selection.skip_scans, selection.min_timestamp);
+
+    // erorile de citire din scanarile sarite conteaza si ele, deci nu folosim nth()
+    for scan in ScanReader::open(path, selection.min_timestamp, selection.duration_ns)? {
+        let scan = scan?;
+        if scan.index == selection.skip_scans {
+            let mut scan_points = scan.points;
+            println!("Sincronizare reusita! Incepem scanarea la t={}", scan_points[0].timestamp_ns);
+            scan_points.shrink_to_fit();
+            return Ok(scan_points);
+        }
+    }
+
+    // magie rust
+    Err("Nu s-au gasit puncte LiDAR după timestamp.".into())
+}
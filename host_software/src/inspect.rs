@@ -0,0 +1,62 @@
+use std::error::Error;
+
+use crate::cli::InspectArgs;
+use crate::data_loader;
+
+// rezumatul unei scanari gasite in fisierul LiDAR
+struct ScanSummary {
+    start_ns: u64,
+    end_ns: u64,
+    points: usize,
+}
+
+// inspect: intervalele de timp din fisiere, rata IMU-ului si primele scanari LiDAR
+pub fn run(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    // fisierele pot fi oricat de lungi: le parcurgem in flux, fara sa le incarcam
+    let mut imu_count = 0usize;
+    let mut imu_bounds: Option<(u64, u64)> = None;
+    for result in data_loader::imu_records(&args.input.imu)? {
+        let m = result?;
+        imu_count += 1;
+        imu_bounds = Some(imu_bounds.map_or((m.timestamp_ns, m.timestamp_ns), |(first, _)| (first, m.timestamp_ns)));
+    }
+
+    println!("IMU: {}", args.input.imu.display());
+    let imu_range = match imu_bounds {
+        Some((first, last)) => {
+            let duration_s = (last - first) as f64 * 1e-9;
+            let rate = if duration_s > 0.0 { (imu_count - 1) as f64 / duration_s } else { 0.0 };
+            println!("  {} masuratori, {} -> {} ({:.2} s, ~{:.1} Hz)",
+                     imu_count, first, last, duration_s, rate);
+            Some((first, last))
+        }
+        None => {
+            println!("  fisier gol");
+            None
+        }
+    };
+
+    // scanarile se delimiteaza ca in data_loader::ScanReader: o scanare noua dupa scan_duration_ms
+    let duration_ns = args.scan_duration_ms * 1_000_000;
+    let mut scans: Vec<ScanSummary> = Vec::new();
+    for scan in data_loader::ScanReader::open(&args.input.lidar, 0, duration_ns)?.take(args.max_scans) {
+        let points = scan?.points;
+        scans.push(ScanSummary {
+            start_ns: points[0].timestamp_ns,
+            end_ns: points.iter().map(|p| p.timestamp_ns).max().unwrap_or(points[0].timestamp_ns),
+            points: points.len(),
+        });
+    }
+
+    println!("LiDAR: {} (primele {} scanari de {} ms)", args.input.lidar.display(), scans.len(), args.scan_duration_ms);
+    for (idx, scan) in scans.iter().enumerate() {
+        // scanarea se poate corecta doar daca IMU-ul o acopera complet
+        let covered = imu_range.is_some_and(|(first, last)| scan.start_ns >= first && scan.end_ns <= last);
+        println!("  #{:<3} {} -> {}  {:>7} puncte  {:>6.1} ms  {}",
+                 idx, scan.start_ns, scan.end_ns, scan.points,
+                 (scan.end_ns - scan.start_ns) as f64 * 1e-6,
+                 if covered { "IMU ok" } else { "fara IMU" });
+    }
+
+    Ok(())
+}
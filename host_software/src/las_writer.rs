@@ -0,0 +1,296 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cloud_writer::CloudPoint;
+
+// LAS 1.4, point data record format 6 (x, y, z, intensity, returns, GPS time), fara VLR-uri
+// coordonatele se scriu ca intregi: valoare = X * scale + offset, iar scale / offset se aleg din limitele norului
+// limitele sunt cunoscute doar la final, deci punctele trec intai printr-un fisier temporar (f64, fara pierderi)
+// si memoria ramane mica oricate scanari s-ar aduna
+
+const HEADER_SIZE: u16 = 375;
+const POINT_FORMAT: u8 = 6;
+const POINT_RECORD_LEN: u16 = 30;
+// o inregistrare din fisierul temporar: x, y, z, timp (f64) + intensitate + scanare
+const TMP_RECORD_LEN: usize = 4 * 8 + 1 + 4;
+// milimetri; scale-ul creste doar daca norul nu incape pe i32
+const BASE_SCALE: f64 = 0.001;
+// bitul 0: timpul e Adjusted Standard GPS Time; bitul 4 (WKT) e obligatoriu pentru formatele 6-10
+const GLOBAL_ENCODING: u16 = 1 | (1 << 4);
+// epoca GPS (6 ian 1980) in timp Unix, secundele de salt GPS - UTC (din 2017) si decalajul "adjusted" (1e9 s)
+const GPS_EPOCH_UNIX_S: i128 = 315_964_800;
+const GPS_LEAP_SECONDS: i128 = 18;
+const GPS_ADJUST_S: i128 = 1_000_000_000;
+
+pub struct LasWriter {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    // None dupa finish
+    tmp: Option<BufWriter<File>>,
+    count: u64,
+    min: [f64; 3],
+    max: [f64; 3],
+}
+
+impl LasWriter {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref().to_path_buf();
+        let tmp_path = path.with_extension("las.tmp");
+        Ok(Self {
+            tmp: Some(BufWriter::new(File::create(&tmp_path)?)),
+            path,
+            tmp_path,
+            count: 0,
+            min: [f64::MAX; 3],
+            max: [f64::MIN; 3],
+        })
+    }
+
+    pub fn write_point(&mut self, p: &CloudPoint) -> Result<(), Box<dyn Error>> {
+        for ((min, max), v) in self.min.iter_mut().zip(self.max.iter_mut()).zip(p.xyz) {
+            *min = f64::min(*min, v);
+            *max = f64::max(*max, v);
+        }
+        let gps_time = adjusted_gps_time(p.timestamp_ns);
+        let tmp = self.tmp.as_mut().expect("LasWriter folosit dupa finish");
+        for v in [p.xyz[0], p.xyz[1], p.xyz[2], gps_time] {
+            tmp.write_all(&v.to_le_bytes())?;
+        }
+        tmp.write_all(&[p.intensity])?;
+        tmp.write_all(&p.scan.to_le_bytes())?;
+        self.count += 1;
+        Ok(())
+    }
+
+    // scrie fisierul LAS final din fisierul temporar
+    pub fn finish(mut self) -> Result<u64, Box<dyn Error>> {
+        if let Some(mut tmp) = self.tmp.take() {
+            tmp.flush()?;
+        }
+        if self.count == 0 {
+            self.min = [0.0; 3];
+            self.max = [0.0; 3];
+        }
+
+        let (scale, offset) = scale_offset(&self.min, &self.max);
+        let mut out = BufWriter::new(File::create(&self.path)?);
+        write_header(&mut out, self.count, &scale, &offset, &self.min, &self.max)?;
+
+        let mut tmp = BufReader::new(File::open(&self.tmp_path)?);
+        let mut record = [0u8; TMP_RECORD_LEN];
+        for _ in 0..self.count {
+            tmp.read_exact(&mut record)?;
+            let f64_at = |i: usize| f64::from_le_bytes(record[i * 8..i * 8 + 8].try_into().unwrap());
+            let xyz = [f64_at(0), f64_at(1), f64_at(2)];
+            let gps_time = f64_at(3);
+            let intensity = record[32];
+            let scan = u32::from_le_bytes(record[33..37].try_into().unwrap());
+
+            for ((v, offset), scale) in xyz.iter().zip(&offset).zip(&scale) {
+                let stored = ((v - offset) / scale).round() as i32;
+                out.write_all(&stored.to_le_bytes())?;
+            }
+            // intensitatea pe 16 biti, cum cere standardul (0..255 -> 0..65535)
+            out.write_all(&(intensity as u16 * 257).to_le_bytes())?;
+            // un singur retur: return number 1 din 1
+            out.write_all(&[0x11])?;
+            // classification flags, canal, directie, margine
+            out.write_all(&[0])?;
+            // clasa 0: creat, neclasificat
+            out.write_all(&[0])?;
+            // user data
+            out.write_all(&[0])?;
+            // scan angle
+            out.write_all(&0i16.to_le_bytes())?;
+            // point source id: indexul scanarii, ca scanarile adunate sa poata fi separate in GIS
+            out.write_all(&(scan.min(u16::MAX as u32) as u16).to_le_bytes())?;
+            out.write_all(&gps_time.to_le_bytes())?;
+        }
+        out.flush()?;
+        drop(tmp);
+        fs::remove_file(&self.tmp_path)?;
+        Ok(self.count)
+    }
+}
+
+// la o eroare (in finish sau inainte) fisierul temporar nu ramane pe disc; dupa un finish reusit e deja sters
+impl Drop for LasWriter {
+    fn drop(&mut self) {
+        self.tmp.take();
+        let _ = fs::remove_file(&self.tmp_path);
+    }
+}
+
+// timestamp-ul Unix al punctului (ns, UTC) -> Adjusted Standard GPS Time: secunde GPS de la epoca GPS minus 1e9
+// calculat in ns intregi, ca f64 sa pastreze rezolutia de microsecunde
+fn adjusted_gps_time(timestamp_ns: u64) -> f64 {
+    let offset_s = GPS_EPOCH_UNIX_S - GPS_LEAP_SECONDS + GPS_ADJUST_S;
+    (timestamp_ns as i128 - offset_s * 1_000_000_000) as f64 / 1e9
+}
+
+// offset-ul in centrul norului (metri intregi), scale-ul cel mai fin (de la 1 mm in sus) la care
+// jumatatea de interval incape pe i32
+fn scale_offset(min: &[f64; 3], max: &[f64; 3]) -> ([f64; 3], [f64; 3]) {
+    let offset: [f64; 3] = std::array::from_fn(|axis| ((min[axis] + max[axis]) / 2.0).round());
+    let scale = std::array::from_fn(|axis| {
+        let half_range = (max[axis] - offset[axis]).max(offset[axis] - min[axis]);
+        let mut scale = BASE_SCALE;
+        while half_range / scale > i32::MAX as f64 {
+            scale *= 10.0;
+        }
+        scale
+    });
+    (scale, offset)
+}
+
+// antetul LAS 1.4 (375 de octeti, little-endian)
+fn write_header(
+    out: &mut impl Write,
+    count: u64,
+    scale: &[f64; 3],
+    offset: &[f64; 3],
+    min: &[f64; 3],
+    max: &[f64; 3],
+) -> Result<(), Box<dyn Error>> {
+    out.write_all(b"LASF")?;
+    out.write_all(&0u16.to_le_bytes())?; // file source id
+    out.write_all(&GLOBAL_ENCODING.to_le_bytes())?;
+    out.write_all(&[0u8; 16])?; // project id (GUID)
+    out.write_all(&[1, 4])?; // versiunea 1.4
+    out.write_all(&fixed_ascii::<32>("OTHER"))?; // system identifier
+    out.write_all(&fixed_ascii::<32>(concat!("host_software ", env!("CARGO_PKG_VERSION"))))?;
+    let (day_of_year, year) = creation_date();
+    out.write_all(&day_of_year.to_le_bytes())?;
+    out.write_all(&year.to_le_bytes())?;
+    out.write_all(&HEADER_SIZE.to_le_bytes())?;
+    out.write_all(&(HEADER_SIZE as u32).to_le_bytes())?; // offset to point data (fara VLR-uri)
+    out.write_all(&0u32.to_le_bytes())?; // numarul de VLR-uri
+    out.write_all(&[POINT_FORMAT])?;
+    out.write_all(&POINT_RECORD_LEN.to_le_bytes())?;
+    // campurile legacy (32 de biti) raman 0 pentru formatele 6-10
+    out.write_all(&0u32.to_le_bytes())?;
+    out.write_all(&[0u8; 5 * 4])?;
+    for v in scale.iter().chain(offset) {
+        out.write_all(&v.to_le_bytes())?;
+    }
+    for (max, min) in max.iter().zip(min) {
+        out.write_all(&max.to_le_bytes())?;
+        out.write_all(&min.to_le_bytes())?;
+    }
+    out.write_all(&0u64.to_le_bytes())?; // start of waveform data packet record
+    out.write_all(&0u64.to_le_bytes())?; // start of first EVLR
+    out.write_all(&0u32.to_le_bytes())?; // numarul de EVLR-uri
+    out.write_all(&count.to_le_bytes())?;
+    // punctele pe numarul returului (15 valori): toate sunt primul retur
+    out.write_all(&count.to_le_bytes())?;
+    out.write_all(&[0u8; 14 * 8])?;
+    Ok(())
+}
+
+// text ASCII pe N octeti, completat cu 0
+fn fixed_ascii<const N: usize>(text: &str) -> [u8; N] {
+    let mut buf = [0u8; N];
+    let len = text.len().min(N);
+    buf[..len].copy_from_slice(&text.as_bytes()[..len]);
+    buf
+}
+
+// ziua din an (1..366) si anul, din ceasul sistemului (UTC)
+fn creation_date() -> (u16, u16) {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86_400) as i64;
+    let mut year = 1970i64;
+    let mut remaining = days;
+    loop {
+        let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+        let year_days = if leap { 366 } else { 365 };
+        if remaining < year_days {
+            break;
+        }
+        remaining -= year_days;
+        year += 1;
+    }
+    ((remaining + 1) as u16, year as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(xyz: [f64; 3], intensity: u8, scan: u32, timestamp_ns: u64) -> CloudPoint {
+        CloudPoint { xyz, intensity, scan, error: 0.0, timestamp_ns }
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn i32_at(bytes: &[u8], at: usize) -> i32 {
+        i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn f64_at(bytes: &[u8], at: usize) -> f64 {
+        f64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn gps_time_is_adjusted_standard() {
+        // 2017-01-01T00:00:00Z = GPS 1167264018 s (18 secunde de salt)
+        let unix_ns = 1_483_228_800u64 * 1_000_000_000;
+        assert_eq!(adjusted_gps_time(unix_ns), 167_264_018.0);
+    }
+
+    #[test]
+    fn header_and_records_round_trip() {
+        let path = std::env::temp_dir().join(format!("las_writer_test_{}.las", std::process::id()));
+        let mut las = LasWriter::create(&path).unwrap();
+        let tmp_path = path.with_extension("las.tmp");
+        las.write_point(&point([10.0, -2.5, 1.0], 255, 3, 1_483_228_800_000_000_000)).unwrap();
+        las.write_point(&point([12.0, -1.5, 3.0], 0, 4, 1_483_228_800_500_000_000)).unwrap();
+        assert_eq!(las.finish().unwrap(), 2);
+        assert!(!tmp_path.exists(), "fisierul temporar a ramas pe disc");
+
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(&bytes[0..4], b"LASF");
+        assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]), GLOBAL_ENCODING);
+        assert_eq!(u16::from_le_bytes([bytes[94], bytes[95]]), HEADER_SIZE);
+        assert_eq!(u32_at(&bytes, 96), HEADER_SIZE as u32);
+        assert_eq!(bytes[104], POINT_FORMAT);
+        assert_eq!(u16::from_le_bytes([bytes[105], bytes[106]]), POINT_RECORD_LEN);
+        assert_eq!(bytes.len(), HEADER_SIZE as usize + 2 * POINT_RECORD_LEN as usize);
+
+        // scale 1 mm, offset in centrul norului (11, -2, 2)
+        for axis in 0..3 {
+            assert_eq!(f64_at(&bytes, 131 + 8 * axis), BASE_SCALE);
+        }
+        assert_eq!([f64_at(&bytes, 155), f64_at(&bytes, 163), f64_at(&bytes, 171)], [11.0, -2.0, 2.0]);
+        // max / min pe x
+        assert_eq!([f64_at(&bytes, 179), f64_at(&bytes, 187)], [12.0, 10.0]);
+        // numarul de puncte (u64) si punctele primului retur
+        assert_eq!(u64::from_le_bytes(bytes[247..255].try_into().unwrap()), 2);
+        assert_eq!(u64::from_le_bytes(bytes[255..263].try_into().unwrap()), 2);
+
+        // primul punct: (10 - 11) / 0.001, intensitatea 255 -> 65535, scanarea in point source id, GPS time
+        let record = &bytes[HEADER_SIZE as usize..];
+        assert_eq!([i32_at(record, 0), i32_at(record, 4), i32_at(record, 8)], [-1000, -500, -1000]);
+        assert_eq!(u16::from_le_bytes([record[12], record[13]]), 65535);
+        assert_eq!(u16::from_le_bytes([record[20], record[21]]), 3);
+        assert_eq!(f64_at(record, 22), 167_264_018.0);
+        let second = &record[POINT_RECORD_LEN as usize..];
+        assert_eq!(f64_at(second, 22), 167_264_018.5);
+    }
+
+    #[test]
+    fn dropped_writer_removes_temp_file() {
+        let path = std::env::temp_dir().join(format!("las_writer_drop_{}.las", std::process::id()));
+        let mut las = LasWriter::create(&path).unwrap();
+        las.write_point(&point([0.0; 3], 1, 0, 0)).unwrap();
+        drop(las);
+        assert!(!path.with_extension("las.tmp").exists());
+        assert!(!path.exists());
+    }
+}
@@ -12,27 +12,42 @@ pub struct Pose {
 
 // 1. calcularea traiectoriei ca vector continuu din datele IMU
 pub fn calculate_trajectory(imu_data: &[ImuMeasurement]) -> Vec<Pose> {
-    let mut trajectory = Vec::new();
-
-    let mut position = Vector3::new(0.0, 0.0, 0.0);
-    let mut velocity = Vector3::new(0.0, 0.0, 0.0);
-    let mut rotation = UnitQuaternion::identity();
+    let mut integrator = TrajectoryIntegrator::default();
+    imu_data.iter().filter_map(|m| integrator.step(m)).collect()
+}
 
-    // vectorul gravitational trebuie scos din masuratori
-    let gravity = Vector3::new(0.0, 0.0, 9.81);
+// starea integrarii IMU, ca traiectoria sa poata fi calculata si pe un flux (inregistrari lungi)
+#[derive(Debug, Clone)]
+pub struct TrajectoryIntegrator {
+    position: Vector3<f64>,
+    velocity: Vector3<f64>,
+    rotation: UnitQuaternion<f64>,
+    prev_time: Option<u64>,
+}
 
-    if imu_data.is_empty() {
-        return trajectory;
+impl Default for TrajectoryIntegrator {
+    fn default() -> Self {
+        Self {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            rotation: UnitQuaternion::identity(),
+            prev_time: None,
+        }
     }
+}
 
-    let mut prev_time = imu_data[0].timestamp_ns;
+impl TrajectoryIntegrator {
+    // un pas de integrare; prima masuratoare doar porneste ceasul, duplicatele sunt sarite
+    pub fn step(&mut self, measurement: &ImuMeasurement) -> Option<Pose> {
+        // vectorul gravitational trebuie scos din masuratori
+        let gravity = Vector3::new(0.0, 0.0, 9.81);
 
-    for measurement in imu_data {
         let current_time = measurement.timestamp_ns;
+        let prev_time = *self.prev_time.get_or_insert(current_time);
 
         // calcul delta t (în secunde)
         let dt_ns = current_time.saturating_sub(prev_time);
-        if dt_ns == 0 { continue; } // trecem peste duplicate
+        if dt_ns == 0 { return None; } // trecem peste duplicate
         let dt = dt_ns as f64 * 1e-9;
 
         // vectorul viteza unghiulara
@@ -44,8 +59,7 @@ pub fn calculate_trajectory(imu_data: &[ImuMeasurement]) -> Vec<Pose> {
 
         // Updatam rotatia curenta ca si quaternion
         let delta_rot = UnitQuaternion::from_scaled_axis(ang_vel * dt);
-        rotation = rotation * delta_rot;
-
+        self.rotation *= delta_rot;
 
         // calculam acceleratia reala ca SI minus gravitatea pentru a trece in SNI
         let acc_local = Vector3::new(
@@ -53,24 +67,22 @@ pub fn calculate_trajectory(imu_data: &[ImuMeasurement]) -> Vec<Pose> {
             measurement.acc_y,
             measurement.acc_z
         );
-        let acc_global = rotation * acc_local - gravity;
+        let acc_global = self.rotation * acc_local - gravity;
 
         // v = v0 + a * dt
-        velocity += acc_global * dt;
+        self.velocity += acc_global * dt;
         // p = p0 + v * dt
-        position += velocity * dt;
+        self.position += self.velocity * dt;
 
-        // salvam starea noua
-        trajectory.push(Pose {
-            timestamp_ns: current_time,
-            rotation,
-            translation: position,
-        });
+        self.prev_time = Some(current_time);
 
-        prev_time = current_time;
+        // starea noua
+        Some(Pose {
+            timestamp_ns: current_time,
+            rotation: self.rotation,
+            translation: self.position,
+        })
     }
-
-    trajectory
 }
 
 // 2. traiectoria devine un B-tree (binary tree dar poate avea mai mult de 2 copii)
@@ -119,4 +131,51 @@ pub fn interpolate_pose(lut: &BTreeMap<u64, Pose>, query_ts: u64) -> Option<Pose
         //iubim Option<>
         _ => None,
     }
-}
\ No newline at end of file
+}
+// vitezele constante pe o scanare, pentru modul cu viteze din hardware
+// ambele sunt exprimate in sistemul senzorului de la inceputul scanarii
+#[derive(Debug, Clone, Copy)]
+pub struct ScanVelocity {
+    pub scan_start_ns: u64,
+    pub omega: Vector3<f64>,    // rad/s, media giroscopului pe scanare
+    pub velocity: Vector3<f64>, // m/s, deplasarea din traiectorie impartita la durata
+}
+
+// estimarea vitezelor pe intervalul [t_start, t_end]
+pub fn estimate_scan_velocity(
+    imu_data: &[ImuMeasurement],
+    lut: &BTreeMap<u64, Pose>,
+    t_start: u64,
+    t_end: u64,
+) -> Option<ScanVelocity> {
+    let in_scan: Vec<_> = imu_data.iter()
+        .filter(|m| m.timestamp_ns >= t_start && m.timestamp_ns <= t_end)
+        .collect();
+    if in_scan.is_empty() || t_end <= t_start {
+        return None;
+    }
+
+    // giroscopul masoara deja in sistemul senzorului, deci media lui e omega
+    let omega = in_scan.iter()
+        .map(|m| Vector3::new(m.ang_vel_x, m.ang_vel_y, m.ang_vel_z))
+        .sum::<Vector3<f64>>() / in_scan.len() as f64;
+
+    // deplasarea din traiectorie, rotita inapoi in sistemul de la inceputul scanarii
+    let start = interpolate_pose(lut, t_start)?;
+    let end = interpolate_pose(lut, t_end)?;
+    let duration = (t_end - t_start) as f64 * 1e-9;
+    let velocity = start.rotation.inverse() * (end.translation - start.translation) / duration;
+
+    Some(ScanVelocity { scan_start_ns: t_start, omega, velocity })
+}
+
+// pose-ul exprimat in sistemul de referinta 'reference': reference^-1 * pose
+// R = Rref^T * R, t = Rref^T * (t - tref)
+pub fn relative_pose(reference: &Pose, pose: &Pose) -> Pose {
+    let inverse = reference.rotation.inverse();
+    Pose {
+        timestamp_ns: pose.timestamp_ns,
+        rotation: inverse * pose.rotation,
+        translation: inverse * (pose.translation - reference.translation),
+    }
+}
@@ -1,45 +1,180 @@
+mod accuracy;
+mod backend;
+mod bus_packer;
+mod cli;
+mod cloud_writer;
+mod compare;
 mod data_loader;
+mod inspect;
+mod las_writer;
 mod lut_gen;
+mod perf_report;
+mod pipeline;
+mod point_decoder;
+mod range_image;
+mod uart_link;
 
 use std::error::Error;
 use std::time::Instant;
-use std::fs::File;
-use std::io::Write;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use clap::Parser;
+
+use fpga_core::{RangeImageConfig, OutputFormat};
+use fpga_core::projection::RANGE_BEAMS;
+use fpga_core::types::DebugSel;
+
+use cli::{Cli, Command, CorrectArgs, Frame};
+use cloud_writer::{CloudFormat, CloudLayout, CloudPoint, CloudWriter};
+use lut_gen::Pose;
+use pipeline::{CorrectedScan, ScanStats, ScanWork};
+
+// histograma erorilor fata de referinta: bin-uri de --accuracy-bin-mm, ultimul numara tot ce e peste
+const ACCURACY_BINS: usize = 50;
+
+// range image: tabelul VLP-16 din hardware, 1024 de coloane (~0.35 grade), distante in centimetri
+const RANGE_COLUMNS: u16 = 1024;
+const RANGE_UNIT_M: f64 = 0.01;
+
+// in modul cu o singura scanare IMU-ul de dinaintea ei se citeste in pasi de 1 s
+const IMU_FILL_STEP_NS: u64 = 1_000_000_000;
+
+// alegem unde ruleaza corectia (--backend):
+//   sim (implicit)     - simularea cycle-accurate a LidarProcessor
+//   uart-sim           - protocolul serial complet, cu nucleul UART simulat in proces
+//   uart:/dev/ttyUSB0  - placa reala, pe portul serial dat
+// cu --coverage, backend-ul sim raporteaza si acoperirea automatului / registrelor din engine,
+// iar cu --compact intoarce coordonatele intregi din etajul de formatare (implicit 16.16, fara rotunjire)
+fn select_backend(args: &CorrectArgs) -> Result<Box<dyn backend::Backend>, Box<dyn Error>> {
+    let arg = args.backend.as_str();
+    match arg {
+        "sim" => {
+            let format = args.compact.map_or_else(OutputFormat::disabled, OutputFormat::from);
+            let sim = backend::SimBackend::new(RangeImageConfig::vlp16(RANGE_COLUMNS)).with_output_format(format);
+            Ok(Box::new(if args.coverage { sim.with_coverage() } else { sim }))
+        }
+        "uart-sim" => Ok(Box::new(uart_link::UartBackend::new(uart_link::SimulatedCore::default()))),
+        _ => match arg.strip_prefix("uart:") {
+            Some(path) => Ok(Box::new(uart_link::UartBackend::new(uart_link::SerialPort::open(path)?))),
+            None => Err(format!("Backend necunoscut: {} (sim, uart-sim sau uart:<port>)", arg).into()),
+        },
+    }
+}
 
-use rhdl::prelude::*;
-use fpga_core::{LidarProcessor, LidarInput, LidarOutput};
-use fpga_core::types::{Vector3 as FpgaVec3, Matrix3x3 as FpgaMat3x3};
+// cati workeri de backend ruleaza in paralel (--jobs, implicit numarul de nuclee)
+// placa reala are un singur port serial, deci acolo e mereu unul
+fn worker_count(args: &CorrectArgs) -> usize {
+    if args.backend.starts_with("uart:") {
+        return 1;
+    }
+    args.jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get())).max(1)
+}
 
-// float -> fixed point (s32)
-fn to_fix(val: f64) -> s32 {
-    let scaled = (val * 65536.0) as i128; // 16.16 format
-    s32::from(scaled.clamp(i32::MIN as i128, i32::MAX as i128))
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    match &cli.command {
+        Command::Correct(args) => run_correct(args),
+        Command::Inspect(args) => inspect::run(args),
+        Command::Compare(args) => compare::run(args),
+    }
 }
 
-// fixed point -> float
-fn from_fix_to_float(val: s32) -> f64 {
-    let raw = val.typed_bits().as_i64().unwrap();
-    (raw as f64) / 65536.0
+// fisierul de iesire, in formatul dat de extensie (.csv, .pcd, .ply, .las)
+// 'scan_column' adauga indexul scanarii (iesirea combinata din --all-scans)
+fn create_cloud(args: &CorrectArgs, path: &Path, scan_column: bool) -> Result<CloudWriter, Box<dyn Error>> {
+    let layout = CloudLayout { scan: scan_column, error: args.error_field };
+    CloudWriter::create(path, CloudFormat::from_path(path, args.binary)?, layout)
 }
-fn main() -> Result<(), Box<dyn Error>> {
-    println!("LiDAR Motion Correction: SINGLE FRAME MODE ");
 
-    // 1. incărcăm IMU-ul primul (ca sa stim timpul de inceput)
-    let start_load = Instant::now();
-    let all_imu = data_loader::load_imu_data("data/raw_imu.csv")?;
+// punctele unei scanari corectate
+// fiecare punct e comparat si cu corectia de referinta in f64
+fn write_scan(
+    out: &mut CloudWriter,
+    scan: &CorrectedScan,
+    range_image: &mut range_image::RangeImage,
+    accuracy: &mut accuracy::AccuracyReport,
+) -> Result<(), Box<dyn Error>> {
+    for (result, source) in &scan.points {
+        // conversie inapoi FPGA -> Host (16.16, sau mm / cm intregi cu --compact)
+        let corrected = point_decoder::decode_point(result);
+        let error = corrected.iter().zip(&source.reference).map(|(c, r)| (c - r).powi(2)).sum::<f64>().sqrt();
+
+        // salvare
+        out.write_point(&CloudPoint {
+            xyz: corrected,
+            intensity: source.intensity,
+            scan: scan.stats.index as u32,
+            error: error as f32,
+            timestamp_ns: source.timestamp_ns,
+        })?;
+        range_image.insert(&result.pixel);
+        // precizia se masoara pe 16.16, nu pe punctul scris (care cu --compact e rotunjit)
+        let (measured, compact) = point_decoder::measured_point(result);
+        accuracy.add(measured, source.reference, compact);
+    }
+    Ok(())
+}
+
+// precizia fata de referinta: afisata mereu, salvata cu --accuracy-report
+fn finish_accuracy(args: &CorrectArgs, accuracy: &accuracy::AccuracyReport) -> Result<(), Box<dyn Error>> {
+    if accuracy.count() == 0 {
+        return Ok(());
+    }
+    accuracy.print();
+    if let Some(path) = &args.accuracy_report {
+        accuracy.write(path)?;
+        println!("Raport de precizie salvat în '{}'", path.display());
+    }
+    println!("------------------------------------------------");
+    Ok(())
+}
+
+// contoarele si acoperirea backend-urilor, la final
+fn print_backend_reports(reports: &pipeline::Reports) {
+    if let Some(perf) = &reports.perf {
+        perf.print();
+        println!("------------------------------------------------");
+    }
+    if let Some(cov) = &reports.coverage {
+        cov.print_summary();
+        println!("------------------------------------------------");
+    }
+}
 
-    if all_imu.is_empty() {
-        return Err("Fisierul IMU este gol!".into());
+fn run_correct(args: &CorrectArgs) -> Result<(), Box<dyn Error>> {
+    let debug_sel = DebugSel::from(args.debug);
+    if debug_sel != DebugSel::Final {
+        println!("Mod depanare: CSV-ul contine valorile {:?}, nu punctele corectate", debug_sel);
     }
+    if args.velocity && args.frame != Frame::ScanStart {
+        println!("Mod viteze: rezultatul e mereu in sistemul scan-start, --frame e ignorat");
+    }
+
+    if args.all_scans {
+        return run_sequence(args);
+    }
+
+    println!("LiDAR Motion Correction: SINGLE FRAME MODE ");
+
+    // 1. deschidem IMU-ul primul (ca sa stim timpul de inceput)
+    let start_load = Instant::now();
+    let mut imu = data_loader::ImuWindow::open(&args.input.imu)?;
 
     // cand a pornit IMU-ul
-    let imu_start_time = all_imu.first().unwrap().timestamp_ns;
+    let imu_start_time = imu.first_timestamp()?.ok_or("Fisierul IMU este gol!")?;
     println!("IMU Start Time: {}", imu_start_time);
 
     // 2. Incarcare scanarea LiDAR, dar cerem să înceapă DUPĂ imu_start_time
     // adaugam un mic buffer (+1ms) ca să fim siguri că avem date de interpolare în stanga
-    let scan_points = data_loader::load_single_scan("data/raw_lidar.csv", imu_start_time + 1_000_000)
-        .expect("Eroare la citirea/sincronizarea lidar data");
+    let selection = data_loader::ScanSelection {
+        min_timestamp: args.scan.start_ns.unwrap_or(imu_start_time + 1_000_000).max(imu_start_time + 1_000_000),
+        skip_scans: args.scan.scan_index,
+        duration_ns: args.scan.scan_duration_ms * 1_000_000,
+    };
+    let scan_points = data_loader::load_scan(&args.input.lidar, &selection)
+        .map_err(|e| format!("Eroare la citirea/sincronizarea lidar data: {}", e))?;
 
     // Aflăm intervalul scanării încărcate
     let t_start = scan_points.first().unwrap().timestamp_ns;
@@ -49,11 +184,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("Interval Scanare: {} -> {}", t_start, t_end);
     println!("Timp Incarcare Total: {:.2?}", start_load.elapsed());
 
-    // 3. filtram IMU-ul pentru acest interval cu margine de eroare
-    let margin_ns = 5_000_000; // 5ms
-    let relevant_imu: Vec<_> = all_imu.into_iter()
-        .filter(|m| m.timestamp_ns >= (t_start - margin_ns) && m.timestamp_ns <= (t_end + margin_ns))
-        .collect();
+    // 3. citim IMU-ul doar pana la acest interval, cu margine de eroare
+    // pe drum uitam ce e inainte de fereastra, ca o scanare tarzie sa nu tina toata inregistrarea in memorie
+    let margin_ns = args.imu_margin_ms * 1_000_000;
+    let window_start = t_start.saturating_sub(margin_ns);
+    let mut filled = imu_start_time;
+    while filled < window_start {
+        filled = (filled + IMU_FILL_STEP_NS).min(window_start);
+        imu.fill_until(filled)?;
+        imu.drop_before(window_start);
+    }
+    imu.fill_until(t_end + margin_ns)?;
+    let relevant_imu = imu.between(window_start, t_end + margin_ns).to_vec();
 
     println!("Date IMU relevante (pentru interpolare): {} măsurători", relevant_imu.len());
 
@@ -69,129 +211,195 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("Pose-LUT generat: {} intrari", pose_lut.len());
 
     // 5. testare + Simulare
-    let mut matched = 0;
-    for point in &scan_points {
-        if let Some(_pose) = lut_gen::interpolate_pose(&pose_lut, point.timestamp_ns) {
-            matched += 1;
-        }
-    }
+    let mut cloud = create_cloud(args, &args.output, false)?;
+    let mut range_image = range_image::RangeImage::new(RANGE_BEAMS, RANGE_COLUMNS as usize, RANGE_UNIT_M);
+
+    // o singura scanare e un singur job (tot pe un LidarProcessor), deci --jobs nu ajuta aici
+    let workers = 1;
+    let work = ScanWork { index: args.scan.scan_index, points: scan_points, imu: relevant_imu, pose_lut };
+    let make_backend = || select_backend(args).map_err(|e| e.to_string());
+    let mut stats: Option<ScanStats> = None;
+    let mut accuracy = accuracy::AccuracyReport::new(args.accuracy_bin_mm * 1e-3, ACCURACY_BINS);
+
+    let start_time = Instant::now();
+    let reports = pipeline::run(args, workers, std::iter::once(Ok(work)), &make_backend, |scan| {
+        write_scan(&mut cloud, &scan, &mut range_image, &mut accuracy)?;
+        stats = Some(scan.stats);
+        Ok(())
+    })?;
+    cloud.finish()?;
+    let stats = stats.ok_or("Scanarea nu a ajuns la iesire")?;
 
     println!("------------------------------------------------");
     println!("REZULTAT SINCRONIZARE:");
     println!("Puncte Corelate cu Succes: {} / {} ({:.2}%)",
-             matched, scan_points.len(), (matched as f64 / scan_points.len() as f64) * 100.0);
+             stats.matched, stats.points, (stats.matched as f64 / stats.points as f64) * 100.0);
     println!("------------------------------------------------");
-
-    if matched > 0 {
-        println!(" Putem trimite datele (Punct + Pose) la FPGA.");
+    println!("Procesare Finalizata în {:.2?} ({} worker)", start_time.elapsed(), workers);
+    println!("Puncte Corectate: {} / {}", stats.corrected, stats.matched);
+    println!("Rezultat salvat în '{}'", args.output.display());
+    // doar backend-urile care intorc pixelul (etajul de proiectie activ) umplu imaginea
+    if range_image.filled() > 0 {
+        range_image.write_pgm(&args.range_image)?;
+        println!("Range image ({} pixeli) salvat în '{}'", range_image.filled(), args.range_image.display());
     }
-    // aici vom colecta rezultatele
-    let mut corrected_cloud = Vec::new();
-    let mut matched_count = 0;
+    println!("------------------------------------------------");
+    finish_accuracy(args, &accuracy)?;
+    print_backend_reports(&reports);
+
+    Ok(())
+}
 
-    let mut file = File::create("data/corrected_cloud.csv")?;
-    writeln!(file, "x,y,z,intensity")?;
+// calea fisierului scanarii 'index' cu --split-scans: data/cloud.csv -> data/cloud_0007.csv
+fn scan_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path.file_stem().map_or_else(|| "scan".into(), |s| s.to_string_lossy().to_string());
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{:04}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}_{:04}", stem, index),
+    };
+    path.with_file_name(name)
+}
 
-    let start_time = Instant::now();
+// scanarile pentru --all-scans, fiecare cu IMU-ul si pose-urile din jurul ei
+// traiectoria e integrata continuu din IMU, deci scanarile in sistemul world formeaza o harta comuna
+// IMU-ul si LiDAR-ul sunt citite in flux, iar pose-urile vechi sunt uitate: memoria nu creste cu lungimea inregistrarii
+struct SequenceSource {
+    scans: data_loader::ScanReader,
+    imu: data_loader::ImuWindow,
+    integrator: lut_gen::TrajectoryIntegrator,
+    pose_lut: BTreeMap<u64, Pose>,
+    margin_ns: u64,
+    first_scan: usize,
+    // cate scanari mai trimitem (--max-scans)
+    remaining: Option<usize>,
+}
+
+impl SequenceSource {
+    fn next_scan(&mut self) -> Result<Option<ScanWork>, Box<dyn Error>> {
+        if self.remaining == Some(0) {
+            return Ok(None);
+        }
 
-    // pipeline
-    for point in &scan_points {
-        // gasim pose-ul interpolat
-        if let Some(pose) = lut_gen::interpolate_pose(&pose_lut, point.timestamp_ns) {
-            matched_count += 1;
-
-            // convertim datele Host -> FPGA (Fixed Point)
-            let p_in = FpgaVec3 {
-                x: to_fix(point.x),
-                y: to_fix(point.y),
-                z: to_fix(point.z),
-            };
-
-            // convertim matricea de rotatie (pose.rotation este [[f64;3];3])
-            let mut r_rows = [[to_fix(0.0); 3]; 3];
-            let rot_matrix = pose.rotation.to_rotation_matrix();
-            for i in 0..3 {
-                for j in 0..3 {
-                    r_rows[i][j] = to_fix(rot_matrix[(i, j)]);
+        for scan in self.scans.by_ref() {
+            let data_loader::Scan { index, points } = scan?;
+            let t_start = points.first().map_or(0, |p| p.timestamp_ns);
+            let t_end = points.last().map_or(0, |p| p.timestamp_ns);
+            let window_start = t_start.saturating_sub(self.margin_ns);
+            let window_end = t_end + self.margin_ns;
+
+            // integram IMU-ul pana dupa scanare, si pentru scanarile sarite (traiectoria e continua)
+            let added = self.imu.fill_until(window_end)?;
+            let window = self.imu.window();
+            for m in &window[window.len() - added..] {
+                if let Some(pose) = self.integrator.step(m) {
+                    self.pose_lut.insert(pose.timestamp_ns, pose);
                 }
             }
-            let rot_in = FpgaMat3x3 { rows: r_rows };
-
-            let trans_in = FpgaVec3 {
-                x: to_fix(pose.translation.x),
-                y: to_fix(pose.translation.y),
-                z: to_fix(pose.translation.z),
-            };
-
-            // construim intrare in fpga virtual
-            let input_active = LidarInput {
-                valid: true,
-                point: p_in,
-                rotation: rot_in,
-                translation: trans_in,
-            };
-
-            // construim intrare in fpga virtual
-            // necesar pentru ca FPGA-ul sa termine calculul fara sa piarda intrarea
-            let input_hold = LidarInput {
-                valid: false,
-                point: p_in,
-                rotation: rot_in,
-                translation: trans_in,
-            };
-
-            // simulam ciclul hardware
-            // replicam scenariul din testele 'fpga_core' (Idle -> Pulse -> Hold)
-            let mut inputs = Vec::new();
-
-            // t0-t1: Idle/Reset (2 cicluri pentru stabilizare)
-            inputs.push(LidarInput::default());
-            inputs.push(LidarInput::default());
-
-            // t2: Impuls Date
-            inputs.push(input_active);
-
-            // t3-t17: Wait/Process
-            for _ in 0..15 {
-                inputs.push(input_hold);
-            }
 
-            // rulam simularea pe acest stream
-            // instantiem un procesor NOU per punct in simularea asta simpla
-            let acc_hdware = LidarProcessor::default();
+            // uitam ce e inainte de fereastra; pastram un pose inainte de ea, pentru interpolare
+            self.imu.drop_before(window_start);
+            let keep_from = self.pose_lut.range(..window_start).next_back().map_or(window_start, |(&ts, _)| ts);
+            self.pose_lut = self.pose_lut.split_off(&keep_from);
 
-            // .with_reset(1) -> reset activ in primul ciclu
-            let stream = inputs.into_iter().with_reset(1).clock_pos_edge(100);
-            let vcd_iter = acc_hdware.run(stream).unwrap();
+            if index < self.first_scan {
+                continue;
+            }
+            if let Some(remaining) = &mut self.remaining {
+                *remaining -= 1;
+            }
+            return Ok(Some(ScanWork {
+                index,
+                points,
+                imu: self.imu.between(window_start, window_end).to_vec(),
+                pose_lut: self.pose_lut.clone(),
+            }));
+        }
+        Ok(None)
+    }
+}
 
-            // cautam output-ul valid
-            for sample_state in vcd_iter {
-                // sample.value este (ClockReset, Input, Output)
-                // 2 este Output-ul procesorului
-                let output = sample_state.value.2;
+impl Iterator for SequenceSource {
+    type Item = Result<ScanWork, String>;
 
-                if output.valid {
-                    let out_vec = output.corrected_point;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_scan().map_err(|e| e.to_string()).transpose()
+    }
+}
 
-                    // conversie inapoi FPGA -> Host
-                    let fx = from_fix_to_float(out_vec.x);
-                    let fy = from_fix_to_float(out_vec.y);
-                    let fz = from_fix_to_float(out_vec.z);
+// --all-scans: toata inregistrarea, scanare cu scanare, prin pipeline-ul pe mai multe fire
+fn run_sequence(args: &CorrectArgs) -> Result<(), Box<dyn Error>> {
+    println!("LiDAR Motion Correction: SEQUENCE MODE ");
+
+    let mut imu = data_loader::ImuWindow::open(&args.input.imu)?;
+    let imu_start_time = imu.first_timestamp()?.ok_or("Fisierul IMU este gol!")?;
+    let min_timestamp = args.scan.start_ns.unwrap_or(0).max(imu_start_time + 1_000_000);
+    let first_scan = args.scan.scan_index;
+    let source = SequenceSource {
+        scans: data_loader::ScanReader::open(&args.input.lidar, min_timestamp, args.scan.scan_duration_ms * 1_000_000)?,
+        imu,
+        integrator: lut_gen::TrajectoryIntegrator::default(),
+        pose_lut: BTreeMap::new(),
+        margin_ns: args.imu_margin_ms * 1_000_000,
+        first_scan,
+        remaining: args.max_scans,
+    };
+
+    // un singur fisier cu coloana 'scan', sau cate unul pe scanare
+    let mut combined = if args.split_scans {
+        None
+    } else {
+        Some(create_cloud(args, &args.output, true)?)
+    };
+
+    let workers = worker_count(args);
+    let make_backend = || select_backend(args).map_err(|e| e.to_string());
+    let mut summary: Vec<ScanStats> = Vec::new();
+    let mut accuracy = accuracy::AccuracyReport::new(args.accuracy_bin_mm * 1e-3, ACCURACY_BINS);
+    let start_time = Instant::now();
 
-                    // salvare
-                    writeln!(file, "{},{},{},{}", fx, fy, fz, point.intensity)?;
-                    corrected_cloud.push((fx, fy, fz));
-                    break;
-                }
+    let reports = pipeline::run(args, workers, source, &make_backend, |scan| {
+        let index = scan.stats.index;
+        let mut range_image = range_image::RangeImage::new(RANGE_BEAMS, RANGE_COLUMNS as usize, RANGE_UNIT_M);
+        match &mut combined {
+            Some(cloud) => write_scan(cloud, &scan, &mut range_image, &mut accuracy)?,
+            None => {
+                let mut cloud = create_cloud(args, &scan_path(&args.output, index), false)?;
+                write_scan(&mut cloud, &scan, &mut range_image, &mut accuracy)?;
+                cloud.finish()?;
             }
         }
+        if range_image.filled() > 0 {
+            range_image.write_pgm(scan_path(&args.range_image, index))?;
+        }
+
+        let stats = scan.stats;
+        println!("Scanarea {}: {} puncte, {} corelate, {} corectate", index, stats.points, stats.matched, stats.corrected);
+        summary.push(stats);
+        Ok(())
+    })?;
+
+    if let Some(cloud) = combined {
+        cloud.finish()?;
     }
 
     println!("------------------------------------------------");
-    println!("Procesare Finalizata în {:.2?}", start_time.elapsed());
-    println!("Puncte Procesate: {} / {}", matched_count, scan_points.len());
-    println!("Rezultat salvat în 'data/corrected_cloud.csv'");
+    println!("{:>6} {:>20} {:>9} {:>9} {:>9}", "scan", "start_ns", "puncte", "corelate", "corectate");
+    for s in &summary {
+        println!("{:>6} {:>20} {:>9} {:>9} {:>9}", s.index, s.start_ns, s.points, s.matched, s.corrected);
+    }
+    let points: usize = summary.iter().map(|s| s.points).sum();
+    let matched: usize = summary.iter().map(|s| s.matched).sum();
+    let corrected: usize = summary.iter().map(|s| s.corrected).sum();
+    println!("Total: {} scanari, {} puncte, {} corelate, {} corectate in {:.2?} ({} workeri)",
+             summary.len(), points, matched, corrected, start_time.elapsed(), workers);
+    if args.split_scans {
+        println!("Rezultate salvate câte unul pe scanare, ex: '{}'", scan_path(&args.output, first_scan).display());
+    } else {
+        println!("Rezultat salvat în '{}'", args.output.display());
+    }
     println!("------------------------------------------------");
+    finish_accuracy(args, &accuracy)?;
+    print_backend_reports(&reports);
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,55 @@
+use fpga_core::PerfStatus;
+use rhdl::prelude::*;
+
+// totalul contoarelor hardware peste toate rularile simularii
+// (fiecare procesor nou porneste contoarele de la 0, deci le adunam)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PerfTotals {
+    pub runs: u64,
+    pub cycles: u64,
+    pub accepted: u64,
+    pub emitted: u64,
+    pub dropped: u64,
+    pub busy_cycles: u64,
+    pub max_latency: u64,
+}
+
+impl PerfTotals {
+    // adaugam status-ul citit la finalul unei rulari
+    pub fn add(&mut self, status: &PerfStatus) {
+        self.runs += 1;
+        self.cycles += status.cycles.raw() as u64;
+        self.accepted += status.accepted.raw() as u64;
+        self.emitted += status.emitted.raw() as u64;
+        self.dropped += status.dropped.raw() as u64;
+        self.busy_cycles += status.busy_cycles.raw() as u64;
+        self.max_latency = self.max_latency.max(status.max_latency.raw() as u64);
+    }
+
+    // totalurile altui backend (ex: alt worker din pipeline)
+    pub fn merge(&mut self, other: &PerfTotals) {
+        self.runs += other.runs;
+        self.cycles += other.cycles;
+        self.accepted += other.accepted;
+        self.emitted += other.emitted;
+        self.dropped += other.dropped;
+        self.busy_cycles += other.busy_cycles;
+        self.max_latency = self.max_latency.max(other.max_latency);
+    }
+
+    pub fn print(&self) {
+        let utilization = if self.cycles > 0 {
+            self.busy_cycles as f64 / self.cycles as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        println!("Contoare Hardware ({} rulari):", self.runs);
+        println!("  Cicluri totale:    {}", self.cycles);
+        println!("  Puncte acceptate:  {}", self.accepted);
+        println!("  Puncte emise:      {}", self.emitted);
+        println!("  Puncte pierdute:   {}", self.dropped);
+        println!("  Cicluri ocupate:   {} ({:.1}%)", self.busy_cycles, utilization);
+        println!("  Latenta maxima:    {} cicluri", self.max_latency);
+    }
+}
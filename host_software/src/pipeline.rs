@@ -0,0 +1,373 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use rhdl::prelude::*;
+use fpga_core::LidarInput;
+use fpga_core::coverage::Coverage;
+use fpga_core::types::{Vector3 as FpgaVec3, Matrix3x3 as FpgaMat3x3, RotFixed, DebugSel};
+
+use crate::backend::{Backend, CoreOutput};
+use crate::cli::{CorrectArgs, Frame};
+use crate::data_loader::{ImuMeasurement, LidarPoint};
+use crate::lut_gen::{self, Pose};
+use crate::perf_report::PerfTotals;
+
+// pipeline-ul de corectie, pe mai multe fire:
+//   citire (scanari + IMU) -> interpolare (pose per punct, fixed point) -> N workeri (backend) -> scriere in ordine
+// etajele sunt legate prin canale marginite, deci memoria ramane mica si pe inregistrari lungi
+// fiecare worker are backend-ul lui si ia cate o scanare intreaga, ca marcajele first / last si scan_done
+// sa vina dintr-un singur LidarProcessor; scanarile sunt reordonate dupa numarul de secventa inainte de scriere
+
+// cate elemente asteapta intre doua etaje (pe worker, pentru canalele workerilor)
+const CHANNEL_DEPTH: usize = 4;
+
+// float -> fixed point (s32)
+fn to_fix(val: f64) -> s32 {
+    let scaled = (val * 65536.0) as i128; // 16.16 format
+    s32::from(scaled.clamp(i32::MIN as i128, i32::MAX as i128))
+}
+
+// float -> coeficient de rotatie (s18)
+// elementele matricei sunt in [-1, 1], deci clamp-ul pe Q2.16 nu pierde nimic in practica
+fn to_fix_rot(val: f64) -> RotFixed {
+    const ROT_MIN: i128 = -(1 << 17);
+    const ROT_MAX: i128 = (1 << 17) - 1;
+    let scaled = (val * 65536.0).round() as i128; // Q2.16 format
+    RotFixed::from(scaled.clamp(ROT_MIN, ROT_MAX))
+}
+
+// vector nalgebra -> Vector3 fixed point
+fn to_fix_vec(v: &nalgebra::Vector3<f64>) -> FpgaVec3 {
+    FpgaVec3 { x: to_fix(v.x), y: to_fix(v.y), z: to_fix(v.z) }
+}
+
+// o scanare citita, cu IMU-ul si pose-urile din jurul ei
+pub struct ScanWork {
+    pub index: usize,
+    pub points: Vec<LidarPoint>,
+    pub imu: Vec<ImuMeasurement>,
+    pub pose_lut: BTreeMap<u64, Pose>,
+}
+
+// rezultatul corectiei unei scanari, pentru rezumat
+#[derive(Debug, Clone, Copy)]
+pub struct ScanStats {
+    pub index: usize,
+    pub start_ns: u64,
+    pub points: usize,
+    pub matched: usize,
+    pub corrected: usize,
+}
+
+// ce pastram din punctul de intrare pana la scriere
+#[derive(Debug, Clone, Copy)]
+pub struct SourcePoint {
+    pub intensity: u8,
+    pub timestamp_ns: u64,
+    // aceeasi valoare ca cea ceruta hardware-ului (vezi --debug), calculata in f64 din pose-ul interpolat
+    pub reference: [f64; 3],
+}
+
+// o scanare corectata, in ordinea punctelor: iesirea hardware-ului + punctul de intrare
+pub struct CorrectedScan {
+    pub stats: ScanStats,
+    pub points: Vec<(CoreOutput, SourcePoint)>,
+}
+
+// contoarele si acoperirea, adunate de la toti workerii
+#[derive(Default)]
+pub struct Reports {
+    pub perf: Option<PerfTotals>,
+    pub coverage: Option<Coverage>,
+}
+
+impl Reports {
+    fn merge(&mut self, other: Reports) {
+        if let Some(perf) = other.perf {
+            self.perf.get_or_insert_with(PerfTotals::default).merge(&perf);
+        }
+        if let Some(cov) = other.coverage {
+            self.coverage.get_or_insert_with(Coverage::new).merge(&cov);
+        }
+    }
+}
+
+// o scanare, gata de trimis la backend
+struct Job {
+    seq: usize,
+    stats: ScanStats,
+    points: Vec<SourcePoint>,
+    inputs: Vec<LidarInput>,
+}
+
+// scanarea procesata de un worker
+struct Done {
+    seq: usize,
+    stats: ScanStats,
+    points: Vec<SourcePoint>,
+    results: Vec<Option<CoreOutput>>,
+}
+
+// corectia de referinta a unui punct, in f64, cu aceeasi selectie de depanare ca hardware-ul
+fn reference_point(point: &LidarPoint, pose: &Pose, debug_sel: DebugSel) -> [f64; 3] {
+    let p = nalgebra::Vector3::new(point.x, point.y, point.z);
+    let v = match debug_sel {
+        DebugSel::Final => pose.rotation * p + pose.translation,
+        DebugSel::Raw => p,
+        DebugSel::Rotated => pose.rotation * p,
+    };
+    [v.x, v.y, v.z]
+}
+
+// pose-ul fiecarui punct din LUT si intrarile hardware-ului (punct + pose in fixed point)
+// fiecare punct primeste si corectia de referinta in f64, cu acelasi pose
+fn prepare_scan(args: &CorrectArgs, work: &ScanWork) -> Result<(ScanStats, Vec<SourcePoint>, Vec<LidarInput>), String> {
+    let pose_lut = &work.pose_lut;
+
+    // Aflăm intervalul scanării
+    let t_start = work.points.first().map_or(0, |p| p.timestamp_ns);
+    let t_end = work.points.last().map_or(0, |p| p.timestamp_ns);
+    let mut stats = ScanStats { index: work.index, start_ns: t_start, points: work.points.len(), matched: 0, corrected: 0 };
+
+    // sistemul de referinta (--frame): pose-urile se exprima fata de senzorul de la inceputul / sfarsitul scanarii
+    let reference = match args.frame {
+        Frame::World => None,
+        Frame::ScanStart => lut_gen::interpolate_pose(pose_lut, t_start),
+        Frame::ScanEnd => lut_gen::interpolate_pose(pose_lut, t_end),
+    };
+    if args.frame != Frame::World && reference.is_none() {
+        return Err(format!("Nu pot calcula pose-ul de referinta pentru --frame (scanarea {})", work.index));
+    }
+
+    // pastram perechile (punct, pose) ca sa stim dinainte care e primul si ultimul punct trimis
+    let matched_points: Vec<_> = work.points.iter()
+        .filter_map(|point| lut_gen::interpolate_pose(pose_lut, point.timestamp_ns).map(|pose| (point, pose)))
+        .map(|(point, pose)| match &reference {
+            Some(r) => (point, lut_gen::relative_pose(r, &pose)),
+            None => (point, pose),
+        })
+        .collect();
+    let matched_count = matched_points.len();
+    stats.matched = matched_count;
+
+    // --velocity: viteze constante pe scanare in loc de un pose complet per punct
+    let scan_velocity = if args.velocity {
+        let vel = lut_gen::estimate_scan_velocity(&work.imu, pose_lut, t_start, t_end)
+            .ok_or_else(|| format!("Nu pot estima vitezele pe scanare din IMU (scanarea {})", work.index))?;
+        Some(vel)
+    } else {
+        None
+    };
+
+    let debug_sel = DebugSel::from(args.debug);
+
+    // in modul cu viteze rezultatul e in sistemul scan-start; referinta foloseste pose-ul interpolat,
+    // deci eroarea raportata include si aproximarea cu viteze constante
+    let velocity_origin = match &scan_velocity {
+        Some(vel) => Some(lut_gen::interpolate_pose(pose_lut, vel.scan_start_ns)
+            .ok_or_else(|| format!("Nu pot interpola pose-ul de inceput al scanarii {}", work.index))?),
+        None => None,
+    };
+
+    let mut sources = Vec::with_capacity(matched_count);
+    let mut inputs = Vec::with_capacity(matched_count);
+    for (idx, (point, pose)) in matched_points.iter().enumerate() {
+        // convertim datele Host -> FPGA (Fixed Point)
+        let p_in = FpgaVec3 {
+            x: to_fix(point.x),
+            y: to_fix(point.y),
+            z: to_fix(point.z),
+        };
+
+        // convertim matricea de rotatie (pose.rotation este [[f64;3];3])
+        let mut r_rows = [[to_fix_rot(0.0); 3]; 3];
+        let rot_matrix = pose.rotation.to_rotation_matrix();
+        for i in 0..3 {
+            for j in 0..3 {
+                r_rows[i][j] = to_fix_rot(rot_matrix[(i, j)]);
+            }
+        }
+        let rot_in = FpgaMat3x3 { rows: r_rows };
+
+        let trans_in = FpgaVec3 {
+            x: to_fix(pose.translation.x),
+            y: to_fix(pose.translation.y),
+            z: to_fix(pose.translation.z),
+        };
+
+        let mut input = LidarInput {
+            point: p_in,
+            rotation: rot_in,
+            translation: trans_in,
+            first: idx == 0,
+            last: idx + 1 == matched_count,
+            debug_sel,
+            ..LidarInput::default()
+        };
+
+        // in modul cu viteze pose-ul e calculat de hardware din omega, v si dt
+        if let Some(vel) = &scan_velocity {
+            input.velocity_mode = true;
+            input.omega = to_fix_vec(&vel.omega);
+            input.velocity = to_fix_vec(&vel.velocity);
+            input.dt = to_fix(point.timestamp_ns.saturating_sub(vel.scan_start_ns) as f64 * 1e-9);
+        }
+
+        let reference = match &velocity_origin {
+            Some(origin) => {
+                let world = lut_gen::interpolate_pose(pose_lut, point.timestamp_ns).unwrap_or(*pose);
+                reference_point(point, &lut_gen::relative_pose(origin, &world), debug_sel)
+            }
+            None => reference_point(point, pose, debug_sel),
+        };
+        sources.push(SourcePoint { intensity: point.intensity, timestamp_ns: point.timestamp_ns, reference });
+        inputs.push(input);
+    }
+
+    Ok((stats, sources, inputs))
+}
+
+// scanarile asteapta in 'pending' pana vin toate cele dinaintea lor, apoi merg la 'sink'
+fn write_in_order(
+    done_rx: Receiver<Done>,
+    sink: &mut impl FnMut(CorrectedScan) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut pending: BTreeMap<usize, Done> = BTreeMap::new();
+    let mut next_seq = 0;
+
+    for done in done_rx {
+        pending.insert(done.seq, done);
+        while let Some(done) = pending.remove(&next_seq) {
+            next_seq += 1;
+            let points: Vec<(CoreOutput, SourcePoint)> = done.results.into_iter()
+                .zip(done.points)
+                .filter_map(|(result, source)| Some((result?, source)))
+                .collect();
+            let stats = ScanStats { corrected: points.len(), ..done.stats };
+            sink(CorrectedScan { stats, points })?;
+        }
+    }
+    Ok(())
+}
+
+// eroarea unui etaj opreste si celelalte etaje
+fn halt_on_err<T>(stop: &AtomicBool, result: Result<T, String>) -> Result<T, String> {
+    if result.is_err() {
+        stop.store(true, Ordering::Relaxed);
+    }
+    result
+}
+
+// un fir care a dat panic
+fn panicked<T>(_: Box<dyn Any + Send>) -> Result<T, String> {
+    Err("Un fir al pipeline-ului s-a oprit neasteptat".to_string())
+}
+
+// rulam pipeline-ul peste scanarile din 'source'; 'sink' primeste scanarile corectate, in ordine
+// fiecare worker isi construieste backend-ul cu 'make_backend' (backend-urile nu trec intre fire)
+// la prima eroare, din orice etaj, toate etajele se opresc
+pub fn run<S>(
+    args: &CorrectArgs,
+    workers: usize,
+    source: S,
+    make_backend: &(dyn Fn() -> Result<Box<dyn Backend>, String> + Sync),
+    mut sink: impl FnMut(CorrectedScan) -> Result<(), Box<dyn Error>>,
+) -> Result<Reports, Box<dyn Error>>
+where
+    S: Iterator<Item = Result<ScanWork, String>> + Send,
+{
+    let workers = workers.max(1);
+    let (work_tx, work_rx) = sync_channel::<ScanWork>(CHANNEL_DEPTH);
+    let (job_tx, job_rx) = sync_channel::<Job>(CHANNEL_DEPTH * workers);
+    let (done_tx, done_rx) = sync_channel::<Done>(CHANNEL_DEPTH * workers);
+    // mpsc are un singur receptor: workerii il impart, lacatul e tinut doar cat se ia o scanare
+    // cand iese ultimul worker receptorul e eliberat, deci interpolarea nu ramane blocata la send
+    let job_rx = Arc::new(Mutex::new(job_rx));
+    let stop = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let stop = &stop;
+
+        // 1. citirea scanarilor
+        let reader = scope.spawn(move || -> Result<(), String> {
+            for work in source {
+                let work = halt_on_err(stop, work)?;
+                if stop.load(Ordering::Relaxed) || work_tx.send(work).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+
+        // 2. interpolarea pose-urilor; cate un job pe scanare
+        // o scanare fara puncte corelate trimite un job gol, ca sa apara in rezumat
+        let interpolator = scope.spawn(move || -> Result<(), String> {
+            for (seq, work) in work_rx.into_iter().enumerate() {
+                let (stats, points, inputs) = halt_on_err(stop, prepare_scan(args, &work))?;
+                let job = Job { seq, stats, points, inputs };
+                if stop.load(Ordering::Relaxed) || job_tx.send(job).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+
+        // 3. workerii, fiecare cu backend-ul lui
+        let handles: Vec<_> = (0..workers).map(|_| {
+            let done_tx = done_tx.clone();
+            let job_rx = Arc::clone(&job_rx);
+            scope.spawn(move || -> Result<Reports, String> {
+                let mut backend = halt_on_err(stop, make_backend())?;
+                while !stop.load(Ordering::Relaxed) {
+                    let next = job_rx.lock().map_err(|_| "Canalul workerilor e blocat".to_string())?.recv();
+                    let Ok(job) = next else { break };
+
+                    let results = halt_on_err(stop, backend.process_batch(&job.inputs).map_err(|e| e.to_string()))?;
+                    let done = Done { seq: job.seq, stats: job.stats, points: job.points, results };
+                    if done_tx.send(done).is_err() {
+                        stop.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                }
+                Ok(Reports { perf: backend.perf().copied(), coverage: backend.coverage().cloned() })
+            })
+        }).collect();
+        drop(done_tx);
+        drop(job_rx);
+
+        // 4. scrierea, pe firul curent
+        // la eroare 'done_rx' e eliberat la iesirea din functie, deci workerii nu raman blocati la send
+        let written = write_in_order(done_rx, &mut sink);
+        if written.is_err() {
+            stop.store(true, Ordering::Relaxed);
+        }
+
+        // asteptam toate firele; prima eroare gasita e cea raportata
+        let mut first_error: Option<String> = None;
+        for result in [reader.join().unwrap_or_else(panicked), interpolator.join().unwrap_or_else(panicked)] {
+            if let Err(e) = result {
+                first_error.get_or_insert(e);
+            }
+        }
+        let mut reports = Reports::default();
+        for handle in handles {
+            match handle.join().unwrap_or_else(panicked) {
+                Ok(worker) => reports.merge(worker),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        written?;
+        match first_error {
+            Some(e) => Err(e.into()),
+            None => Ok(reports),
+        }
+    })
+}
@@ -0,0 +1,77 @@
+use rhdl::prelude::*;
+use fpga_core::types::Vector3 as FpgaVec3;
+use fpga_core::{CompactPoint, CompactUnit};
+
+use crate::backend::CoreOutput;
+use crate::bus_packer::BusOutput;
+
+// decodarea punctului corectat in metri
+// cu etajul de formatare activ (fpga_core/src/format.rs) hardware-ul trimite intregi in mm / cm,
+// altfel coordonatele vin in 16.16
+
+// fixed point 16.16 -> metri
+pub fn fixed_to_m(val: s32) -> f64 {
+    let raw = val.typed_bits().as_i64().unwrap();
+    (raw as f64) / 65536.0
+}
+
+// metri per unitate a formatului compact
+pub fn unit_m(unit: CompactUnit) -> f64 {
+    match unit {
+        CompactUnit::Millimetre => 0.001,
+        CompactUnit::Centimetre => 0.01,
+    }
+}
+
+// coordonatele intregi din CompactPoint.data (extinse cu semn)
+pub fn compact_units(point: &CompactPoint) -> [i32; 3] {
+    let data = point.data.raw();
+    if point.wide {
+        [0, 32, 64].map(|shift| (data >> shift) as u32 as i32)
+    } else {
+        [0, 16, 32].map(|shift| (data >> shift) as u16 as i16 as i32)
+    }
+}
+
+// formatul compact in metri, None daca etajul nu era activ
+pub fn decode_compact(point: &CompactPoint) -> Option<[f64; 3]> {
+    if !point.valid {
+        return None;
+    }
+    let scale = unit_m(point.unit);
+    Some(compact_units(point).map(|v| v as f64 * scale))
+}
+
+// pachetul de pe magistrala: cu bitul compact, cuvintele 1..=3 sunt CompactPoint.data
+pub fn compact_from_bus(out: &BusOutput) -> CompactPoint {
+    if !out.compact {
+        return CompactPoint::default();
+    }
+    let data = out.x as u32 as u128 | ((out.y as u32 as u128) << 32) | ((out.z as u32 as u128) << 64);
+    CompactPoint {
+        valid: true,
+        wide: out.compact_wide,
+        unit: if out.compact_cm { CompactUnit::Centimetre } else { CompactUnit::Millimetre },
+        data: b96::from(data),
+    }
+}
+
+pub fn decode_fixed(point: &FpgaVec3) -> [f64; 3] {
+    [fixed_to_m(point.x), fixed_to_m(point.y), fixed_to_m(point.z)]
+}
+
+// punctul unui rezultat: formatul compact daca exista, altfel 16.16
+pub fn decode_point(out: &CoreOutput) -> [f64; 3] {
+    decode_compact(&out.compact)
+        .or_else(|| out.point.as_ref().map(decode_fixed))
+        .unwrap_or_default()
+}
+
+// punctul pe care se masoara precizia: 16.16 (corrected_point), inainte de rotunjirea formatului compact
+// intoarce si daca a trebuit folosit formatul compact (doar pe magistrala, unde 16.16 lipseste)
+pub fn measured_point(out: &CoreOutput) -> ([f64; 3], bool) {
+    match &out.point {
+        Some(point) => (decode_fixed(point), false),
+        None => (decode_compact(&out.compact).unwrap_or_default(), true),
+    }
+}
@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use rhdl::prelude::*;
+use fpga_core::RangePixel;
+
+// imaginea de distante asamblata din pixelii calculati de etajul de proiectie din hardware
+// fiecare pixel pastreaza cel mai apropiat punct (ca la un z-buffer), 0 = fara punct
+pub struct RangeImage {
+    rows: usize,
+    columns: usize,
+    // metri per unitate in imagine (0.01 => centimetri, maxim ~655 m)
+    unit_m: f64,
+    data: Vec<u16>,
+}
+
+impl RangeImage {
+    pub fn new(rows: usize, columns: usize, unit_m: f64) -> Self {
+        Self { rows, columns, unit_m, data: vec![0; rows * columns] }
+    }
+
+    // pixelii invalizi sau din afara imaginii sunt ignorati
+    pub fn insert(&mut self, pixel: &RangePixel) {
+        let row = pixel.row.raw() as usize;
+        let column = pixel.column.raw() as usize;
+        if !pixel.valid || row >= self.rows || column >= self.columns {
+            return;
+        }
+
+        let range_m = pixel.range.typed_bits().as_i64().unwrap() as f64 / 65536.0;
+        // distanta 0 e rezervata pentru "fara punct"
+        let value = (range_m / self.unit_m).round().clamp(1.0, u16::MAX as f64) as u16;
+
+        let cell = &mut self.data[row * self.columns + column];
+        if *cell == 0 || value < *cell {
+            *cell = value;
+        }
+    }
+
+    pub fn filled(&self) -> usize {
+        self.data.iter().filter(|&&v| v != 0).count()
+    }
+
+    // PGM binar (P5) pe 16 biti, big-endian cum cere formatul
+    pub fn write_pgm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path.as_ref())?);
+        write!(out, "P5\n{} {}\n65535\n", self.columns, self.rows)?;
+        for value in &self.data {
+            out.write_all(&value.to_be_bytes())?;
+        }
+        out.flush()
+    }
+}
@@ -0,0 +1,311 @@
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use rhdl::prelude::*;
+use fpga_core::{LidarInput, RangePixel};
+use fpga_core::bus::OUTPUT_WORDS;
+use fpga_core::sim::cycle_samples;
+use fpga_core::types::Vector3 as FpgaVec3;
+use fpga_core::uart::{UartLidarCore, UartLinkIn, FRAME_SYNC, REQUEST_LEN, RESPONSE_LEN};
+
+use crate::backend::{check_context, Backend, CoreOutput};
+use crate::bus_packer::{pack_input_words, unpack_output_words};
+use crate::point_decoder::compact_from_bus;
+
+// clientul pentru protocolul serial din fpga_core/src/uart.rs
+// cadru: 0xA5, LEN, payload (cuvinte little-endian), CRC-8 (poly 0x07) peste LEN + payload
+
+// CRC-8 identic cu crc8_update din hardware
+pub fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+pub fn encode_request(input: &LidarInput) -> Vec<u8> {
+    let mut frame = vec![FRAME_SYNC, REQUEST_LEN];
+    for word in pack_input_words(input) {
+        frame.extend_from_slice(&word.to_le_bytes());
+    }
+    let crc = crc8(&frame[1..]);
+    frame.push(crc);
+    frame
+}
+
+// cauta primul cadru de raspuns corect in octetii primiti
+pub fn decode_response(bytes: &[u8]) -> Option<[u32; OUTPUT_WORDS]> {
+    let frame_len = RESPONSE_LEN as usize + 3;
+
+    for frame in bytes.windows(frame_len) {
+        if frame[0] != FRAME_SYNC || frame[1] != RESPONSE_LEN {
+            continue;
+        }
+        if crc8(&frame[1..frame_len - 1]) != frame[frame_len - 1] {
+            continue;
+        }
+
+        let mut words = [0u32; OUTPUT_WORDS];
+        for (k, word) in words.iter_mut().enumerate() {
+            let b = &frame[2 + 4 * k..6 + 4 * k];
+            *word = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        return Some(words);
+    }
+    None
+}
+
+// un transport trimite un cadru de cerere si intoarce octetii primiti ca raspuns
+pub trait Transport {
+    fn exchange(&mut self, request: &[u8]) -> Result<Vec<u8>, Box<dyn Error>>;
+}
+
+// viteza liniei, aceeasi ca in nucleul UART de pe placa
+const BAUD_RATE: u32 = 115200;
+
+// port serial real (ex: /dev/ttyUSB0)
+// la deschidere portul e trecut in mod raw (octetii cadrelor nu trebuie interpretati ca text)
+// cu 'min 0 time 1': un read() fara date se intoarce dupa cel mult 0.1 s, deci timeout-ul din exchange() se verifica
+pub struct SerialPort {
+    port: File,
+    timeout: Duration,
+}
+
+impl SerialPort {
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let status = Command::new("stty")
+            .args(["-F", path, &BAUD_RATE.to_string(), "raw", "-echo", "min", "0", "time", "1"])
+            .status()
+            .map_err(|e| format!("Nu pot rula stty pentru {}: {}", path, e))?;
+        if !status.success() {
+            return Err(format!("Nu pot configura portul serial {} (stty: {})", path, status).into());
+        }
+
+        let port = OpenOptions::new().read(true).write(true).open(path)
+            .map_err(|e| format!("Nu pot deschide portul serial {}: {}", path, e))?;
+        Ok(Self { port, timeout: Duration::from_millis(500) })
+    }
+}
+
+impl Transport for SerialPort {
+    fn exchange(&mut self, request: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.port.write_all(request)?;
+        self.port.flush()?;
+
+        let frame_len = RESPONSE_LEN as usize + 3;
+        let mut received = Vec::new();
+        let mut buf = [0u8; 64];
+        let start = Instant::now();
+
+        while start.elapsed() < self.timeout {
+            let n = self.port.read(&mut buf)?;
+            received.extend_from_slice(&buf[..n]);
+            if decode_response(&received).is_some() || received.len() > 4 * frame_len {
+                break;
+            }
+        }
+        Ok(received)
+    }
+}
+
+// nucleul UART simulat in proces: octetii sunt transformati in niveluri pe linia rx,
+// UartLidarCore ruleaza cycle-accurate, iar linia tx e decodata inapoi in octeti
+// fiecare schimb porneste de la reset, la fel ca simularea per punct
+pub struct SimulatedCore {
+    pub clks_per_bit: u16,
+}
+
+impl Default for SimulatedCore {
+    fn default() -> Self {
+        // valoare mica, ca simularea sa ramana rapida (pe placa ar fi 868 @ 100 MHz / 115200)
+        Self { clks_per_bit: 8 }
+    }
+}
+
+impl SimulatedCore {
+    // 8N1: start 0, date LSB first, stop 1
+    fn line_levels(&self, bytes: &[u8]) -> Vec<bool> {
+        let bit = self.clks_per_bit as usize;
+        let mut levels = vec![true; 4 * bit];
+        for &byte in bytes {
+            levels.extend(std::iter::repeat(false).take(bit));
+            for i in 0..8 {
+                levels.extend(std::iter::repeat((byte >> i) & 1 == 1).take(bit));
+            }
+            levels.extend(std::iter::repeat(true).take(bit));
+        }
+        levels
+    }
+
+    // receptor UART software pe nivelurile liniei tx, esantionat la mijlocul fiecarui bit
+    fn decode_line(&self, levels: &[bool]) -> Vec<u8> {
+        let bit = self.clks_per_bit as usize;
+        let mut bytes = Vec::new();
+        let mut i = 1;
+
+        while i + 10 * bit <= levels.len() {
+            if levels[i - 1] && !levels[i] {
+                let mid = i + bit / 2;
+                let mut byte = 0u8;
+                for k in 0..8 {
+                    if levels[mid + (k + 1) * bit] {
+                        byte |= 1 << k;
+                    }
+                }
+                if levels[mid + 9 * bit] {
+                    bytes.push(byte);
+                }
+                i = mid + 9 * bit;
+            } else {
+                i += 1;
+            }
+        }
+        bytes
+    }
+}
+
+impl Transport for SimulatedCore {
+    fn exchange(&mut self, request: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let bit = self.clks_per_bit as usize;
+        let clks_per_bit = b16::from(self.clks_per_bit as u128);
+
+        let mut levels = self.line_levels(request);
+        // timp pentru procesare + cadrul de raspuns (19 octeti x 10 biti) cu margine
+        levels.extend(std::iter::repeat(true).take((RESPONSE_LEN as usize + 3) * 10 * bit + 64 * bit));
+
+        let inputs: Vec<UartLinkIn> = levels.into_iter()
+            .map(|rx_line| UartLinkIn { rx_line, clks_per_bit })
+            .collect();
+
+        let uut = UartLidarCore::default();
+        let stream = inputs.into_iter().with_reset(1).clock_pos_edge(100);
+        let tx_levels: Vec<bool> = cycle_samples(uut.run(stream)?)
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect();
+
+        Ok(self.decode_line(&tx_levels))
+    }
+}
+
+// backend care trimite fiecare punct printr-un transport serial
+// un raspuns lipsa sau cu CRC gresit se intoarce ca None
+pub struct UartBackend<T: Transport> {
+    transport: T,
+}
+
+impl<T: Transport> UartBackend<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+}
+
+impl<T: Transport> Backend for UartBackend<T> {
+    fn process(&mut self, input: &LidarInput) -> Result<Option<CoreOutput>, Box<dyn Error>> {
+        let request = LidarInput {
+            valid: true,
+            pose_write: true,
+            pose_swap: true,
+            ..*input
+        };
+
+        let response = self.transport.exchange(&encode_request(&request))?;
+        let Some(words) = decode_response(&response) else {
+            return Ok(None);
+        };
+
+        let out = unpack_output_words(&words);
+        if !out.valid {
+            return Ok(None);
+        }
+
+        // in modul compact cuvintele 1..=3 sunt unitatile intregi, 16.16 nu se transmite
+        let point = (!out.compact).then(|| FpgaVec3 {
+            x: s32::from(out.x as i128),
+            y: s32::from(out.y as i128),
+            z: s32::from(out.z as i128),
+        });
+        let result = CoreOutput {
+            point,
+            scan_done: out.scan_done,
+            // pachetul serial nu contine pixelul (vezi fpga_core/src/projection.rs)
+            pixel: RangePixel::default(),
+            compact: compact_from_bus(&out),
+            ctx_id: out.ctx_id,
+        };
+        check_context(input, &result)?;
+        Ok(Some(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fpga_core::types::RotFixed;
+    use fpga_core::Matrix3x3;
+
+    fn response_frame(words: &[u32; OUTPUT_WORDS]) -> Vec<u8> {
+        let mut frame = vec![FRAME_SYNC, RESPONSE_LEN];
+        for word in words {
+            frame.extend_from_slice(&word.to_le_bytes());
+        }
+        frame.push(crc8(&frame[1..]));
+        frame
+    }
+
+    #[test]
+    fn crc8_check_value() {
+        // valoarea de verificare standard pentru CRC-8 (poly 0x07, init 0)
+        assert_eq!(crc8(b"123456789"), 0xF4);
+    }
+
+    #[test]
+    fn request_frame_layout() {
+        let frame = encode_request(&LidarInput::default());
+        assert_eq!(frame.len(), REQUEST_LEN as usize + 3);
+        assert_eq!((frame[0], frame[1]), (FRAME_SYNC, REQUEST_LEN));
+        assert_eq!(crc8(&frame[1..frame.len() - 1]), frame[frame.len() - 1]);
+    }
+
+    #[test]
+    fn response_found_after_noise_and_rejected_with_bad_crc() {
+        let words = [1, 0xDEAD_BEEF, 2, 3];
+        let mut bytes = vec![0x00, FRAME_SYNC, 0x13];
+        bytes.extend(response_frame(&words));
+        assert_eq!(decode_response(&bytes), Some(words));
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert_eq!(decode_response(&bytes), None);
+    }
+
+    #[test]
+    fn software_uart_round_trip() {
+        let core = SimulatedCore::default();
+        let bytes = [0x00, 0xFF, FRAME_SYNC, 0x5A, 0x81];
+        assert_eq!(core.decode_line(&core.line_levels(&bytes)), bytes);
+    }
+
+    // cadrul complet prin UartLidarCore simulat: rotatia de 90 grade pe z, (10, 0, 0) -> (0, 10, 0)
+    #[test]
+    fn simulated_core_answers_request() {
+        let one = RotFixed::from(65_536i128);
+        let zero = RotFixed::default();
+        let input = LidarInput {
+            point: FpgaVec3 { x: s32::from(655_360i128), ..FpgaVec3::default() },
+            rotation: Matrix3x3 { rows: [[zero, -one, zero], [one, zero, zero], [zero, zero, one]] },
+            ..LidarInput::default()
+        };
+
+        let mut backend = UartBackend::new(SimulatedCore::default());
+        let out = backend.process(&input).unwrap().expect("nucleul simulat nu a raspuns");
+        assert_eq!(out.point, Some(FpgaVec3 { x: s32::from(0i128), y: s32::from(655_360i128), z: s32::from(0i128) }));
+    }
+}